@@ -0,0 +1,322 @@
+use crate::error::{Error, ErrorStage};
+use dtflib::xdg;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A single destination saved before it got overwritten or removed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+  /// Where the file used to live
+  pub path: PathBuf,
+  /// Where the copy lives, relative to the run directory
+  pub stored: PathBuf,
+  pub mode: u32,
+  pub uid: u32,
+  pub gid: u32,
+  /// Target of the destination if it was a symlink, there is no copy then
+  #[serde(default)]
+  pub symlink: Option<PathBuf>,
+}
+
+/// Backups of a single run, stored under `<state dir>/backups/<run-id>/`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Backups {
+  pub dir: PathBuf,
+
+  #[serde(skip)]
+  pub entries: Vec<Entry>,
+
+  /// Whether the run directory is ours already, see `claim`
+  #[serde(skip)]
+  claimed: bool,
+
+  /// Who the run directory is created on behalf of, the user behind the sudo child
+  #[serde(skip)]
+  owner: Option<(u32, u32)>,
+}
+
+/// Formats unix time as `YYYYMMDDTHHMMSSZ`
+fn timestamp(secs: u64) -> String {
+  let days = (secs / 86400) as i64;
+  let rem = secs % 86400;
+
+  // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+  let z = days + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z - era * 146097;
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+  format!(
+    "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+    year,
+    month,
+    day,
+    rem / 3600,
+    rem % 3600 / 60,
+    rem % 60
+  )
+}
+
+pub fn backups_dir(home_dir: &Path) -> PathBuf {
+  xdg::state_dir(home_dir).join("backups")
+}
+
+/// Path of the file inside the run directory, mirroring its absolute path
+fn stored_path(path: &Path) -> PathBuf {
+  let mut p = PathBuf::from("files");
+
+  for one in path.components() {
+    if let Component::Normal(val) = one {
+      p.push(val);
+    }
+  }
+
+  p
+}
+
+impl Backups {
+  /// Backups for a new run, nothing is created on disk until something is saved
+  pub fn new(home_dir: &Path) -> Self {
+    let root = backups_dir(home_dir);
+    let secs = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_secs())
+      .unwrap_or(0);
+
+    Self {
+      dir: root.join(timestamp(secs)),
+      entries: vec![],
+      claimed: false,
+      owner: None,
+    }
+  }
+
+  /// Backups of a run that has been started by someone else, i.e. the main process. The
+  /// run directory is there already unless there is an `owner` to create it for
+  pub fn at(dir: PathBuf, owner: Option<(u32, u32)>) -> Self {
+    Self {
+      dir,
+      entries: vec![],
+      claimed: owner.is_none(),
+      owner,
+    }
+  }
+
+  /// Creates the run directory, moving on to `<run-id>-2`, `<run-id>-3` and so on when
+  /// another run within the same second got there first
+  fn claim(&mut self) -> io::Result<()> {
+    if self.claimed {
+      return Ok(());
+    }
+
+    let root = self.dir.parent().unwrap().to_path_buf();
+    let id = self.dir.file_name().unwrap().to_str().unwrap().to_owned();
+    fs::create_dir_all(&root)?;
+
+    let mut n = 1;
+
+    loop {
+      match fs::create_dir(&self.dir) {
+        Ok(_) => break,
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+          n += 1;
+          self.dir = root.join(format!("{}-{}", id, n));
+        }
+        Err(e) => return Err(e),
+      }
+    }
+
+    if let Some((uid, gid)) = self.owner {
+      std::os::unix::fs::chown(&self.dir, Some(uid), Some(gid))?;
+    }
+
+    self.claimed = true;
+    Ok(())
+  }
+
+  /// Creates the directory the runs live in on behalf of the current user, so that the
+  /// sudo child doesn't end up owning it. The run directory waits for the first backup
+  pub fn prepare(&self) -> io::Result<()> {
+    fs::create_dir_all(self.dir.parent().unwrap())
+  }
+
+  /// Who the sudo child is to create the run directory for, nobody once it is there
+  pub fn owner(&self) -> Option<(u32, u32)> {
+    match self.claimed {
+      true => None,
+      false => Some(unsafe { (libc::getuid(), libc::getgid()) }),
+    }
+  }
+
+  /// Takes in what the sudo child has saved, along with the run directory if it is the
+  /// one that has created it
+  pub fn extend(&mut self, dir: Option<PathBuf>, entries: Vec<Entry>) {
+    if let Some(dir) = dir {
+      self.dir = dir;
+      self.claimed = true;
+    }
+
+    self.entries.extend(entries);
+  }
+
+  /// The run directory, if this one has created it on behalf of someone else
+  pub fn created(&self) -> Option<PathBuf> {
+    match (self.owner, self.claimed) {
+      (Some(_), true) => Some(self.dir.clone()),
+      _ => None,
+    }
+  }
+
+  /// Saves whatever is at `path` right now, does nothing if there is nothing there
+  pub fn save(&mut self, path: &Path) -> Result<(), Error> {
+    self._save(path).map_err(|e| Error {
+      kind: e.kind().into(),
+      message: e.to_string(),
+      stage: ErrorStage::Backup,
+    })
+  }
+
+  fn _save(&mut self, path: &Path) -> io::Result<()> {
+    let meta = match fs::symlink_metadata(path) {
+      Ok(meta) => meta,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+      Err(e) => return Err(e),
+    };
+
+    let stored = stored_path(path);
+
+    let symlink = match meta.file_type().is_symlink() {
+      true => Some(fs::read_link(path)?),
+      false => {
+        self.claim()?;
+        let to = self.dir.join(&stored);
+        fs::create_dir_all(to.parent().unwrap())?;
+        fs::copy(path, &to)?;
+        None
+      }
+    };
+
+    self.entries.push(Entry {
+      path: path.to_path_buf(),
+      stored,
+      mode: meta.permissions().mode(),
+      uid: meta.uid(),
+      gid: meta.gid(),
+      symlink,
+    });
+
+    Ok(())
+  }
+
+  /// Writes the index of the run, it is what `restore` reads later
+  pub fn finish(&mut self) -> io::Result<()> {
+    if self.entries.is_empty() {
+      return Ok(());
+    }
+
+    self.claim()?;
+
+    // a file that the main process failed to remove is saved once more by the sudo child
+    let mut entries: Vec<&Entry> = vec![];
+    for entry in &self.entries {
+      if !entries.iter().any(|one| one.path == entry.path) {
+        entries.push(entry);
+      }
+    }
+
+    let json = serde_json::to_string_pretty(&entries)?;
+    fs::write(self.dir.join("index.json"), json)
+  }
+}
+
+/// Reads the index of a past run
+pub fn read_index(run_dir: &Path) -> io::Result<Vec<Entry>> {
+  let f = fs::File::open(run_dir.join("index.json"))?;
+  Ok(serde_json::from_reader(f)?)
+}
+
+/// Puts a single entry back where it came from
+pub fn restore(run_dir: &Path, entry: &Entry) -> io::Result<()> {
+  match fs::symlink_metadata(&entry.path) {
+    Ok(_) => fs::remove_file(&entry.path)?,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+    Err(e) => return Err(e),
+  }
+
+  match &entry.symlink {
+    Some(target) => std::os::unix::fs::symlink(target, &entry.path)?,
+    None => {
+      fs::copy(run_dir.join(&entry.stored), &entry.path)?;
+      fs::set_permissions(&entry.path, fs::Permissions::from_mode(entry.mode))?;
+    }
+  }
+
+  if unsafe { libc::geteuid() } == 0 {
+    std::os::unix::fs::lchown(&entry.path, Some(entry.uid), Some(entry.gid))?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test_timestamp {
+  use super::timestamp;
+
+  #[test]
+  fn should_format_unix_time_as_utc() {
+    assert_eq!(timestamp(0), "19700101T000000Z");
+    assert_eq!(timestamp(951782400), "20000229T000000Z");
+    assert_eq!(timestamp(1792321202), "20261018T110002Z");
+  }
+}
+
+#[cfg(test)]
+mod test_claim {
+  use super::Backups;
+  use std::fs;
+
+  #[test]
+  fn should_move_on_when_the_run_dir_is_taken() {
+    let home_dir = std::env::temp_dir().join(format!("dtf-claim-{}", std::process::id()));
+
+    let mut one = Backups::new(&home_dir);
+    let mut two = Backups::new(&home_dir);
+    one.dir = home_dir.join("backups").join("20261018T110002Z");
+    two.dir = one.dir.clone();
+
+    one.claim().unwrap();
+    two.claim().unwrap();
+
+    assert_eq!(two.dir, home_dir.join("backups").join("20261018T110002Z-2"));
+    assert!(two.dir.is_dir());
+
+    fs::remove_dir_all(&home_dir).unwrap();
+  }
+
+  #[test]
+  fn should_leave_the_run_dir_to_the_first_backup() {
+    let home_dir = std::env::temp_dir().join(format!("dtf-prepare-{}", std::process::id()));
+
+    let main = Backups::new(&home_dir);
+    main.prepare().unwrap();
+    assert!(!main.dir.exists());
+
+    // the sudo child creates it for the main process, which learns where it ended up
+    let mut child = Backups::at(main.dir.clone(), main.owner());
+    assert_eq!(child.created(), None);
+    child.claim().unwrap();
+    assert_eq!(child.created(), Some(main.dir.clone()));
+    assert!(main.dir.is_dir());
+
+    fs::remove_dir_all(&home_dir).unwrap();
+  }
+}
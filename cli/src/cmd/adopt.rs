@@ -0,0 +1,82 @@
+use crate::{error, Report};
+use colored::Colorize;
+use dtflib::{Context, DotFile};
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::Path;
+
+fn move_file(from: &Path, to: &Path) -> std::result::Result<(), error::Error> {
+  let fail = |kind: ErrorKind, message: String| error::Error {
+    kind: kind.into(),
+    message,
+    stage: error::ErrorStage::Adopt,
+  };
+
+  match fs::symlink_metadata(from) {
+    Ok(meta) if meta.file_type().is_file() => {}
+    Ok(_) => {
+      return Err(fail(
+        ErrorKind::Other,
+        "only regular files can be adopted".to_owned(),
+      ))
+    }
+    Err(e) => return Err(fail(e.kind(), e.to_string())),
+  }
+
+  if to.exists() {
+    return Err(fail(
+      ErrorKind::AlreadyExists,
+      "the repo already has a file with the same name".to_owned(),
+    ));
+  }
+
+  if let Some(dir) = to.parent() {
+    fs::create_dir_all(dir).map_err(|e| fail(e.kind(), e.to_string()))?;
+  }
+
+  match fs::rename(from, to) {
+    Ok(_) => Ok(()),
+    // the repo lives on another filesystem
+    Err(e) if e.raw_os_error() == Some(libc::EXDEV) => {
+      fs::copy(from, to).map_err(|e| fail(e.kind(), e.to_string()))?;
+      fs::remove_file(from).map_err(|e| fail(e.kind(), e.to_string()))
+    }
+    Err(e) => Err(fail(e.kind(), e.to_string())),
+  }
+}
+
+/// Moves the file from its destination into the repo, it's up to the caller to link it back
+pub fn adopt(_cx: &Context, dotfile: &DotFile) -> Result<()> {
+  match move_file(&dotfile.dst_file_path(), &dotfile.src_file_path()) {
+    Ok(_) => {
+      println!(
+        "|> {}: {}",
+        dotfile.name.bold(),
+        format!("adopted into {}", dotfile.src.to_str().unwrap()).green()
+      );
+      Ok(())
+    }
+    Err(err) => {
+      Report::print(dotfile, &err);
+      std::process::exit(1);
+    }
+  }
+}
+
+/// Puts an adopted file back where it was taken from, when the config couldn't take it in
+pub fn disown(_cx: &Context, dotfile: &DotFile) -> Result<()> {
+  match move_file(&dotfile.src_file_path(), &dotfile.dst_file_path()) {
+    Ok(_) => {
+      println!(
+        "|> {}: {}",
+        dotfile.name.bold(),
+        "moved back, the config was left as it was".yellow()
+      );
+      Ok(())
+    }
+    Err(err) => {
+      Report::print(dotfile, &err);
+      std::process::exit(1);
+    }
+  }
+}
@@ -0,0 +1,146 @@
+use crate::backup::{self, Entry};
+use crate::{error, sudo};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+pub fn backups_list(home_dir: &Path) -> Result<()> {
+  let root = backup::backups_dir(home_dir);
+
+  let mut runs: Vec<PathBuf> = match fs::read_dir(&root) {
+    Ok(dir) => dir
+      .filter_map(|one| one.ok())
+      .map(|one| one.path())
+      .filter(|one| one.is_dir())
+      .collect(),
+    Err(e) if e.kind() == ErrorKind::NotFound => vec![],
+    Err(e) => return Err(e),
+  };
+  runs.sort_unstable();
+
+  for run in runs {
+    let entries = match backup::read_index(&run) {
+      Ok(entries) => entries,
+      Err(_) => continue,
+    };
+
+    println!(
+      "{} {}",
+      run.file_name().unwrap().to_str().unwrap().bold(),
+      format!("({} files)", entries.len()).dimmed()
+    );
+
+    for entry in entries {
+      println!("\t{}", entry.path.to_str().unwrap().dimmed());
+    }
+  }
+
+  Ok(())
+}
+
+/// What the main process hands over to the sudo child when restoring
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RestoreRequest {
+  pub run_dir: PathBuf,
+  pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RestoreReport {
+  path: PathBuf,
+  error: Option<error::Error>,
+}
+
+fn restore_all(run_dir: &Path, entries: Vec<Entry>) -> (Vec<RestoreReport>, Vec<Entry>) {
+  let mut reports = Vec::with_capacity(entries.len());
+  let mut denied = vec![];
+
+  for entry in entries {
+    match backup::restore(run_dir, &entry) {
+      Ok(_) => reports.push(RestoreReport {
+        path: entry.path,
+        error: None,
+      }),
+      Err(e) if e.kind() == ErrorKind::PermissionDenied => denied.push(entry),
+      Err(e) => reports.push(RestoreReport {
+        path: entry.path,
+        error: Some(error::Error {
+          kind: e.kind().into(),
+          message: e.to_string(),
+          stage: error::ErrorStage::Restore,
+        }),
+      }),
+    }
+  }
+
+  (reports, denied)
+}
+
+/// Puts the files saved during the run back, all of them or only the given `paths`
+pub fn restore(home_dir: &Path, run_id: &str, paths: &[PathBuf], child: bool) -> Result<()> {
+  if child {
+    let mut request_json = String::with_capacity(256);
+    std::io::stdin().read_line(&mut request_json)?;
+    let request: RestoreRequest = serde_json::from_str(&request_json)?;
+
+    let (mut reports, denied) = restore_all(&request.run_dir, request.entries);
+    reports.extend(denied.into_iter().map(|entry| RestoreReport {
+      path: entry.path,
+      error: Some(error::Error {
+        kind: error::ErrorKind::PermissionDenied,
+        message: "permission denied".to_owned(),
+        stage: error::ErrorStage::Restore,
+      }),
+    }));
+
+    println!("{}", serde_json::to_string(&reports)?);
+    return Ok(());
+  }
+
+  // the id names a directory of the backups, it can't lead anywhere else
+  if run_id.is_empty() || run_id.contains('/') || run_id.contains("..") {
+    return Err(Error::new(
+      ErrorKind::InvalidInput,
+      format!("`{}` is not a run id, see `dtf backups list`", run_id),
+    ));
+  }
+
+  let run_dir = backup::backups_dir(home_dir).join(run_id);
+  let entries: Vec<Entry> = backup::read_index(&run_dir)?
+    .into_iter()
+    .filter(|entry| paths.is_empty() || paths.iter().any(|path| path == &entry.path))
+    .collect();
+
+  let (mut reports, denied) = restore_all(&run_dir, entries);
+
+  if !denied.is_empty() {
+    let sreports: Vec<RestoreReport> = sudo::sudo(&RestoreRequest {
+      run_dir,
+      entries: denied,
+    })?;
+    reports.extend(sreports);
+  }
+
+  let mut exit = 0;
+
+  for report in reports {
+    let path = report.path.to_str().unwrap();
+
+    match report.error {
+      None => println!("|> {}: {}", path.bold(), "restored".green()),
+      Some(err) => {
+        exit = 1;
+        println!("|> {}: {}", path.bold(), err.message.red());
+        println!("   - Error occured while trying to {}", err.stage);
+      }
+    }
+  }
+
+  if exit == 1 {
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
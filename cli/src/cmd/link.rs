@@ -1,59 +1,222 @@
-use crate::{hard_link, sudo, Report};
-use dtflib::{Context, DotFile};
+use super::rollback::roll_back;
+use crate::execute::execute;
+use crate::journal::Journal;
+use crate::plan::{self, Action, Operation, Plan};
+use crate::{backup::Backups, copy, sudo, Report, Request};
+use colored::Colorize;
+use dtflib::manifest::{Manifest, Record};
+use dtflib::{Context, DotFile, LinkMode};
 use std::collections::HashMap;
-use std::io::Result;
-
-pub fn link(cx: &Context, dotfiles: &HashMap<u32, DotFile>, force: bool) -> Result<()> {
-  let ref mut denied: HashMap<u32, &DotFile> = HashMap::new();
-  let mut reports: Vec<Report> = Vec::with_capacity(dotfiles.len());
-
-  for (id, dotfile) in dotfiles {
-    match hard_link::hard_link(cx, dotfile, force, None) {
-      Ok(_) => reports.push(Report {
-        dotfile_id: *id,
-        error: None,
-      }),
-      Err(e) => match e.kind {
-        hard_link::ErrorKind::PermissionDenied => {
-          denied.insert(*id, dotfile);
-        }
-        _ => reports.push(Report {
-          dotfile_id: *id,
-          error: Some(e),
-        }),
-      },
-    }
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+
+/// Run-wide options of the `ln` command
+#[derive(Debug, Clone, Copy)]
+pub struct LinkOpts {
+  pub force: bool,
+  pub mode: LinkMode,
+  pub relative: bool,
+  pub dry_run: bool,
+  /// Takes everything back once anything fails
+  pub atomic: bool,
+  pub create_dirs: bool,
+  pub dir_mode: Option<u32>,
+}
+
+/// Executes what the main process is allowed to, leaving privileged operations and
+/// whatever turns out to be denied to the sudo child. With a `journal`, the sudo child
+/// is not bothered once the main process has failed
+pub(super) fn run(
+  plan: Plan,
+  backups: &mut Backups,
+  journal: Option<&Journal>,
+) -> Result<Vec<Report>> {
+  let (privileged, own): (Vec<Operation>, Vec<Operation>) =
+    plan.operations.into_iter().partition(|op| op.privileged);
+
+  let (mut reports, mut denied) = execute(&own, backups, true, journal);
+
+  for op in privileged {
+    denied.entry(op.dotfile_id).or_default().push(op);
   }
 
-  if cx.is_main() {
-    if !denied.is_empty() {
-      let sreports = sudo(denied)?;
-      reports.extend(sreports);
-    }
+  reports.extend(plan.reports);
+
+  if journal.is_none() || reports.iter().all(|report| report.is_ok()) {
+    sudo::escalate(denied, &mut reports, backups, journal)?;
+  }
+
+  reports.sort_by_key(|report| report.dotfile_id);
+
+  Ok(reports)
+}
 
-    let mut exit = 0;
+/// Executes the operations the main process has handed over to the sudo child
+pub fn apply(request: Request<Vec<Operation>>) -> Result<()> {
+  let mut ids: Vec<&u32> = request.dotfiles.keys().collect();
+  ids.sort_unstable();
 
-    for report in &reports {
-      if report.is_ok() {
+  let operations: Vec<Operation> = ids
+    .into_iter()
+    .flat_map(|id| request.dotfiles.get(id).unwrap().iter().cloned())
+    .collect();
+
+  let backups = &mut Backups::at(request.backup_dir, request.backup_owner);
+  let journal = request.journal.map(Journal::at);
+  let (reports, _) = execute(&operations, backups, false, journal.as_ref());
+  sudo::respond(reports, backups);
+
+  Ok(())
+}
+
+/// Writes down what has been installed, so that later runs know which destinations
+/// belong to dtf and `pull` could tell which side of a copy has been changed. Created
+/// `dirs` are written down too, for `unlink` to clean them up
+pub(super) fn remember(
+  cx: &Context,
+  dotfiles: &HashMap<u32, DotFile>,
+  reports: &[Report],
+  dirs: &[PathBuf],
+  opts: &LinkOpts,
+) -> Result<()> {
+  let mut manifest = Manifest::load(cx.home_dir)?;
+
+  for dir in dirs.iter().filter(|dir| dir.is_dir()) {
+    manifest.record_dir(cx.config_path, dir.clone());
+  }
+
+  for report in reports.iter().filter(|report| report.is_ok()) {
+    let dotfile = dotfiles.get(&report.dotfile_id).unwrap();
+    let strategy = dotfile.link_mode(opts.mode);
+    let dst = dotfile.dst_file_path();
+
+    let found = match strategy {
+      LinkMode::Copy => copy::hash_file(&dotfile.src_file_path()).map(|hash| (None, Some(hash))),
+      _ => fs::symlink_metadata(&dst).map(|meta| (Some(meta.ino()), None)),
+    };
+
+    // the file is in place all the same, only later runs won't know it is dtf's
+    let (inode, hash) = match found {
+      Ok(val) => val,
+      Err(e) => {
+        eprintln!(
+          "|> {}: {}",
+          dotfile.name.bold(),
+          format!("linked, but can't be remembered: {}", e).yellow()
+        );
         continue;
       }
+    };
 
-      exit = 1;
+    manifest.record(
+      cx.config_path,
+      Record {
+        src: dotfile.src_file_path(),
+        dst,
+        strategy,
+        inode,
+        hash,
+        timestamp: Record::now(),
+      },
+    );
+  }
 
-      let dotfile = dotfiles.get(&report.dotfile_id).unwrap();
-      let err = report.error.as_ref().unwrap();
+  manifest.save()
+}
+
+pub fn link(
+  cx: &Context,
+  dotfiles: &HashMap<u32, DotFile>,
+  opts: &LinkOpts,
+  backups: &mut Backups,
+) -> Result<()> {
+  if !link_all(cx, dotfiles, opts, backups)? {
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
+
+/// Links the dotfiles, telling whether every one of them got linked rather than exiting,
+/// so that the caller can carry on. A failed atomic run is rolled back and exits still
+pub fn link_all(
+  cx: &Context,
+  dotfiles: &HashMap<u32, DotFile>,
+  opts: &LinkOpts,
+  backups: &mut Backups,
+) -> Result<bool> {
+  let plan = plan::plan(dotfiles.values(), opts);
 
-      Report::print(dotfile, err);
+  if opts.dry_run {
+    plan.print(dotfiles);
+    return Ok(plan.reports.is_empty());
+  }
+
+  // nothing is worth starting when it is already known to fail
+  if opts.atomic && !plan.reports.is_empty() {
+    for report in &plan.reports {
+      let dotfile = dotfiles.get(&report.dotfile_id).unwrap();
+      Report::print(dotfile, report.error.as_ref().unwrap());
     }
 
-    if exit == 1 {
-      std::process::exit(1);
+    std::process::exit(1);
+  }
+
+  let journal = match opts.atomic {
+    true => match Journal::begin(cx.home_dir) {
+      Ok(journal) => Some(journal),
+      Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+        eprintln!(
+          "|> {}",
+          "the last `ln --atomic` did not make it to the end, run `dtf rollback` first".red()
+        );
+        std::process::exit(1);
+      }
+      Err(e) => return Err(e),
+    },
+    false => None,
+  };
+
+  let dirs: Vec<PathBuf> = plan
+    .operations
+    .iter()
+    .filter_map(|op| match &op.action {
+      Action::CreateDir { path, .. } => Some(path.clone()),
+      _ => None,
+    })
+    .collect();
+
+  let reports = run(plan, backups, journal.as_ref())?;
+  backups.finish()?;
+
+  let mut exit = 0;
+
+  for report in &reports {
+    if report.is_ok() {
+      continue;
     }
 
-    Ok(())
-  } else {
-    let stdout = serde_json::to_string(&reports).unwrap();
-    println!("{}", stdout);
-    Ok(())
+    exit = 1;
+
+    let dotfile = dotfiles.get(&report.dotfile_id).unwrap();
+    let err = report.error.as_ref().unwrap();
+
+    Report::print(dotfile, err);
   }
+
+  if let Some(journal) = journal {
+    match exit {
+      1 => {
+        roll_back(journal)?;
+        std::process::exit(1);
+      }
+      _ => journal.close()?,
+    }
+  }
+
+  remember(cx, dotfiles, &reports, &dirs, opts)?;
+
+  Ok(exit == 0)
 }
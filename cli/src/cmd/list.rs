@@ -1,11 +1,28 @@
 use colored::Colorize;
-use dtflib::{Context, DotFile};
+use dtflib::{Context, DotFile, LinkMode};
 use std::collections::HashMap;
+use std::fs;
 use std::io::Result;
 
-pub fn list(_cx: &Context, dotfiles: &HashMap<u32, DotFile>) -> Result<()> {
-  for (_, dotfile) in dotfiles {
-    println!("{}", dotfile.name.bold());
+pub fn list(
+  _cx: &Context,
+  dotfiles: &HashMap<u32, DotFile>,
+  mode: LinkMode,
+  relative: bool,
+  explain: bool,
+) -> Result<()> {
+  for dotfile in dotfiles.values() {
+    let mut line = dotfile.name.bold().to_string();
+
+    if let Some(dst_name) = &dotfile.dst_name {
+      line = format!("{} {}", line, format!("as {}", dst_name).dimmed());
+    }
+
+    if let Some(pattern) = &dotfile.pattern {
+      line = format!("{} {}", line, format!("(by `{}`)", pattern).dimmed());
+    }
+
+    println!("{}", line);
     println!(
       "\tfrom {}",
       dotfile.src_file_path().to_str().unwrap().dimmed()
@@ -14,7 +31,58 @@ pub fn list(_cx: &Context, dotfiles: &HashMap<u32, DotFile>) -> Result<()> {
       "\t  to {}",
       dotfile.dst_file_path().to_str().unwrap().dimmed()
     );
+
+    let mode = dotfile.link_mode(mode);
+    match (mode, dotfile.is_relative(relative)) {
+      (LinkMode::Symlink, true) => println!("\t  as {}", "relative symlink".dimmed()),
+      _ => println!("\t  as {}", mode.to_string().dimmed()),
+    }
+
+    if explain {
+      print_candidates(dotfile);
+    }
   }
 
   Ok(())
 }
+
+/// Tells where else the file has been looked for, and why the source it ended up with won
+fn print_candidates(dotfile: &DotFile) {
+  if dotfile.candidates.is_empty() {
+    println!(
+      "\t{}",
+      "the only source directory its targets give".dimmed()
+    );
+    return;
+  }
+
+  println!("\t{}", "looked in, the most specific target first".dimmed());
+
+  let mut picked = false;
+
+  for dir in &dotfile.candidates {
+    let path = dir.join(dotfile.name.as_ref());
+    let exists = fs::symlink_metadata(&path).is_ok();
+    let path = path.to_str().unwrap();
+
+    if !picked && exists && dir == &dotfile.src {
+      picked = true;
+      println!(
+        "\t  {} {}",
+        path,
+        "picked, the first one that has it".green()
+      );
+    } else if exists {
+      println!("\t  {} {}", path, "found, but outranked".dimmed());
+    } else {
+      println!("\t  {} {}", path, "missing".dimmed());
+    }
+  }
+
+  if !picked {
+    println!(
+      "\t{}",
+      "none of them has it, the source of the picked target stays".yellow()
+    );
+  }
+}
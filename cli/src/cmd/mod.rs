@@ -1,8 +1,32 @@
+mod adopt;
+pub use adopt::{adopt, disown};
+
+mod backups;
+pub use backups::{backups_list, restore};
+
 mod link;
-pub use link::link;
+pub use link::{apply, link, link_all, LinkOpts};
 
 mod list;
 pub use list::list;
 
+mod prune;
+pub use prune::prune;
+
+mod pull;
+pub use pull::pull;
+
+mod repair;
+pub use repair::{repair, Prefer};
+
+mod rollback;
+pub use rollback::{recover, rollback};
+
 mod show;
 pub use show::show;
+
+mod status;
+pub use status::status;
+
+mod unlink;
+pub use unlink::unlink;
@@ -0,0 +1,207 @@
+use crate::{backup::Backups, error, inspect, sudo, Report};
+use colored::Colorize;
+use dtflib::manifest::{Manifest, Record};
+use dtflib::{Context, DotFile};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::PathBuf;
+
+fn remove_one(dotfile: &DotFile, backups: &mut Backups) -> std::result::Result<(), error::Error> {
+  let dst = &dotfile.dst_file_path();
+
+  backups.save(dst)?;
+
+  match fs::remove_file(dst) {
+    Ok(_) => Ok(()),
+    Err(e) => Err(error::Error {
+      kind: e.kind().into(),
+      message: e.to_string(),
+      stage: error::ErrorStage::RemoveFile,
+    }),
+  }
+}
+
+fn remove_all<'a>(
+  dotfiles: &'a HashMap<u32, DotFile>,
+  backups: &mut Backups,
+) -> (Vec<Report>, HashMap<u32, &'a DotFile<'a>>) {
+  let mut denied: HashMap<u32, &DotFile> = HashMap::new();
+  let mut reports: Vec<Report> = Vec::with_capacity(dotfiles.len());
+
+  for (id, dotfile) in dotfiles {
+    match remove_one(dotfile, backups) {
+      Ok(_) => reports.push(Report {
+        dotfile_id: *id,
+        error: None,
+      }),
+      Err(e) => match e.kind {
+        error::ErrorKind::PermissionDenied => {
+          denied.insert(*id, dotfile);
+        }
+        _ => reports.push(Report {
+          dotfile_id: *id,
+          error: Some(e),
+        }),
+      },
+    }
+  }
+
+  (reports, denied)
+}
+
+/// Picks the records that are no longer in the config, forgetting the ones whose
+/// destination is gone already. The ones that are not safe to remove stay remembered,
+/// so do the `kept` ones, of blocks only left out on their conditions
+fn stale<'a>(
+  cx: &Context,
+  manifest: &'a Manifest,
+  dotfiles: &HashMap<u32, DotFile>,
+  kept: &[PathBuf],
+) -> (Vec<&'a Record>, Vec<&'a Record>) {
+  let current: HashSet<PathBuf> = dotfiles
+    .values()
+    .map(|dotfile| dotfile.dst_file_path())
+    .chain(kept.iter().cloned())
+    .collect();
+
+  let mut records: Vec<&Record> = manifest
+    .records(cx.config_path)
+    .into_iter()
+    .filter(|record| !current.contains(&record.dst))
+    .collect();
+  records.sort_unstable_by(|a, b| a.dst.cmp(&b.dst));
+
+  let mut prunable = vec![];
+  let mut forgotten = vec![];
+
+  for record in records {
+    let dst = record.dst.to_str().unwrap();
+
+    match inspect::is_recorded(record) {
+      Ok(true) => prunable.push(record),
+      Ok(false) => println!(
+        "|> {}: {}",
+        dst.bold(),
+        "destination was modified, leaving it alone".yellow()
+      ),
+      Err(e) if e.kind() == ErrorKind::NotFound => {
+        println!("|> {}: {}", dst.bold(), "already gone".dimmed());
+        forgotten.push(record);
+      }
+      Err(e) => println!(
+        "|> {}: {}",
+        dst.bold(),
+        format!(
+          "can't tell whether it was modified, leaving it alone ({})",
+          e
+        )
+        .yellow()
+      ),
+    }
+  }
+
+  (prunable, forgotten)
+}
+
+/// Removes destinations dtf has installed before, which are not in the config anymore
+pub fn prune(
+  cx: &Context,
+  dotfiles: &HashMap<u32, DotFile>,
+  kept: &[PathBuf],
+  dry_run: bool,
+  backups: &mut Backups,
+) -> Result<()> {
+  if !cx.is_main() {
+    let (reports, _) = remove_all(dotfiles, backups);
+    sudo::respond(reports, backups);
+    return Ok(());
+  }
+
+  let mut manifest = Manifest::load(cx.home_dir)?;
+  let (prunable, forgotten) = stale(cx, &manifest, dotfiles, kept);
+
+  if dry_run {
+    for record in prunable {
+      println!(
+        "|> {}: {}",
+        record.dst.to_str().unwrap().bold(),
+        "would be pruned".yellow()
+      );
+    }
+
+    return Ok(());
+  }
+
+  let forgotten: Vec<PathBuf> = forgotten.iter().map(|record| record.dst.clone()).collect();
+
+  let file_name = |path: &PathBuf| -> String {
+    let name = path.file_name().unwrap_or_default();
+    name.to_str().unwrap().to_owned()
+  };
+
+  let mut stale: HashMap<u32, DotFile> = HashMap::with_capacity(prunable.len());
+
+  for (i, record) in prunable.iter().enumerate() {
+    let id = i as u32 + 1;
+
+    stale.insert(
+      id,
+      DotFile {
+        id,
+        name: file_name(&record.src).into(),
+        dst_name: Some(file_name(&record.dst).into()),
+        src: record.src.parent().map(PathBuf::from).unwrap_or_default(),
+        dst: record.dst.parent().map(PathBuf::from).unwrap_or_default(),
+        link: Some(record.strategy),
+        relative: None,
+        mkdir: None,
+        dir_mode: None,
+        pattern: None,
+        candidates: vec![],
+      },
+    );
+  }
+
+  let (mut reports, denied) = remove_all(&stale, backups);
+
+  let args = vec![
+    std::env::args().next().unwrap_or_else(|| "dtf".to_owned()),
+    "prune".to_owned(),
+    cx.config_path.to_str().unwrap().to_owned(),
+  ];
+  sudo::escalate_with(args, denied, &mut reports, backups, None)?;
+  backups.finish()?;
+
+  for dst in forgotten {
+    manifest.forget(cx.config_path, &dst);
+  }
+
+  reports.sort_unstable_by_key(|report| report.dotfile_id);
+
+  let mut exit = 0;
+
+  for report in &reports {
+    let dotfile = stale.get(&report.dotfile_id).unwrap();
+    let dst = dotfile.dst_file_path();
+
+    match &report.error {
+      None => {
+        manifest.forget(cx.config_path, &dst);
+        println!("|> {}: {}", dst.to_str().unwrap().bold(), "pruned".green());
+      }
+      Some(err) => {
+        exit = 1;
+        Report::print(dotfile, err);
+      }
+    }
+  }
+
+  manifest.save()?;
+
+  if exit == 1 {
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,137 @@
+use crate::{copy, error, Report};
+use colored::Colorize;
+use dtflib::manifest::{Manifest, Record};
+use dtflib::{Context, DotFile, LinkMode};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Result;
+
+/// What happened to the copied file since dtf has copied it
+enum Change {
+  /// Neither side has moved on
+  None,
+  /// Only the destination was edited, so it can be safely pulled
+  Dst,
+  /// Only the source was edited, so `ln` should push it
+  Src,
+  /// Both sides were edited, one would have to pick a winner by hand
+  Both,
+}
+
+fn change(recorded: Option<&str>, src: &str, dst: &str) -> Change {
+  if src == dst {
+    return Change::None;
+  }
+
+  match recorded {
+    Some(hash) if hash == src => Change::Dst,
+    Some(hash) if hash == dst => Change::Src,
+    _ => Change::Both,
+  }
+}
+
+pub fn pull(cx: &Context, dotfiles: &HashMap<u32, DotFile>, mode: LinkMode) -> Result<()> {
+  let mut manifest = Manifest::load(cx.home_dir)?;
+  let mut exit = 0;
+  let mut changed = false;
+
+  for dotfile in dotfiles.values() {
+    if dotfile.link_mode(mode) != LinkMode::Copy {
+      continue;
+    }
+
+    let src = &dotfile.src_file_path();
+    let dst = &dotfile.dst_file_path();
+
+    if !dst.exists() {
+      println!("|> {}: {}", dotfile.name.bold(), "not copied yet".dimmed());
+      continue;
+    }
+
+    // a file that can't be read is told about, the rest are pulled all the same
+    let hashes = copy::hash_file(src).and_then(|src| Ok((src, copy::hash_file(dst)?)));
+    let (src_hash, dst_hash) = match hashes {
+      Ok(hashes) => hashes,
+      Err(e) => {
+        exit = 1;
+        Report::print(
+          dotfile,
+          &error::Error {
+            kind: e.kind().into(),
+            message: e.to_string(),
+            stage: error::ErrorStage::Pull,
+          },
+        );
+        continue;
+      }
+    };
+
+    let recorded = manifest
+      .get(cx.config_path, dst)
+      .and_then(|record| record.hash.as_deref());
+
+    let remember = Record {
+      src: src.clone(),
+      dst: dst.clone(),
+      strategy: LinkMode::Copy,
+      inode: None,
+      hash: Some(dst_hash.clone()),
+      timestamp: Record::now(),
+    };
+
+    match change(recorded, &src_hash, &dst_hash) {
+      // only worth a record when the manifest doesn't know the hash yet
+      Change::None if recorded != Some(dst_hash.as_str()) => {
+        manifest.record(cx.config_path, remember);
+        changed = true;
+      }
+      Change::None => {}
+      Change::Src => {
+        println!(
+          "|> {}: {}",
+          dotfile.name.bold(),
+          "source is newer, run `ln -f` to push it".dimmed()
+        );
+      }
+      Change::Dst => match fs::copy(dst, src) {
+        Ok(_) => {
+          println!("|> {}: {}", dotfile.name.bold(), "pulled".green());
+          manifest.record(cx.config_path, remember);
+          changed = true;
+        }
+        Err(e) => {
+          exit = 1;
+          Report::print(
+            dotfile,
+            &error::Error {
+              kind: e.kind().into(),
+              message: e.to_string(),
+              stage: error::ErrorStage::Pull,
+            },
+          );
+        }
+      },
+      Change::Both => {
+        exit = 1;
+        Report::print(
+          dotfile,
+          &error::Error {
+            kind: error::ErrorKind::Conflict,
+            message: "both source and destination were changed".to_owned(),
+            stage: error::ErrorStage::Pull,
+          },
+        );
+      }
+    }
+  }
+
+  if changed {
+    manifest.save()?;
+  }
+
+  if exit == 1 {
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,202 @@
+use super::link::{remember, run, LinkOpts};
+use crate::inspect::{self, State};
+use crate::{backup::Backups, error, plan, Report};
+use colored::Colorize;
+use dtflib::{Context, DotFile, LinkMode};
+use similar::{ChangeTag, TextDiff};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, prelude::*, Result};
+use std::str::FromStr;
+
+/// Which side of a broken link should survive the repair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prefer {
+  /// Re-link the destination to the source, dropping destination changes
+  Src,
+  /// Adopt the destination content into the repo and then re-link
+  Dst,
+}
+
+impl Prefer {
+  pub fn variants<'a>() -> &'a [&'a str] {
+    &["src", "dst"]
+  }
+}
+
+impl FromStr for Prefer {
+  type Err = String;
+
+  fn from_str(val: &str) -> std::result::Result<Self, Self::Err> {
+    match val {
+      "src" => Ok(Self::Src),
+      "dst" => Ok(Self::Dst),
+      _ => Err(format!("unknown side `{}`", val)),
+    }
+  }
+}
+
+fn print_newer(dotfile: &DotFile) {
+  let src = fs::metadata(dotfile.src_file_path()).and_then(|meta| meta.modified());
+  let dst = fs::metadata(dotfile.dst_file_path()).and_then(|meta| meta.modified());
+
+  match (src, dst) {
+    (Ok(src), Ok(dst)) if src > dst => println!("   - {}", "source is newer".yellow()),
+    (Ok(src), Ok(dst)) if src < dst => println!("   - {}", "destination is newer".yellow()),
+    (Ok(_), Ok(_)) => println!(
+      "   - {}",
+      "both sides were modified at the same time".yellow()
+    ),
+    (Err(e), _) | (_, Err(e)) => println!(
+      "   - {}",
+      format!("can't tell which side is newer: {}", e).yellow()
+    ),
+  }
+}
+
+fn print_diff(dotfile: &DotFile) -> Result<()> {
+  let src = fs::read(dotfile.src_file_path())?;
+  let dst = fs::read(dotfile.dst_file_path())?;
+
+  let (src, dst) = match (std::str::from_utf8(&src), std::str::from_utf8(&dst)) {
+    (Ok(src), Ok(dst)) => (src, dst),
+    _ => {
+      println!("     {}", "binary files differ".dimmed());
+      return Ok(());
+    }
+  };
+
+  let diff = TextDiff::from_lines(src, dst);
+
+  for change in diff.iter_all_changes() {
+    let line = change.to_string_lossy();
+    let line = line.trim_end_matches('\n');
+
+    match change.tag() {
+      ChangeTag::Delete => println!("     {}", format!("-{}", line).red()),
+      ChangeTag::Insert => println!("     {}", format!("+{}", line).green()),
+      ChangeTag::Equal => println!("     {}", format!(" {}", line).dimmed()),
+    }
+  }
+
+  Ok(())
+}
+
+fn ask(dotfile: &DotFile) -> Result<Option<Prefer>> {
+  loop {
+    print!(
+      "   keep {}ource, keep {}estination or s{}ip? ",
+      "s".bold(),
+      "d".bold(),
+      "k".bold()
+    );
+    io::stdout().flush()?;
+
+    let mut answer = String::with_capacity(4);
+    if io::stdin().read_line(&mut answer)? == 0 {
+      return Ok(None);
+    }
+
+    match answer.trim() {
+      "s" | "src" => return Ok(Some(Prefer::Src)),
+      "d" | "dst" => return Ok(Some(Prefer::Dst)),
+      "k" | "skip" | "" => return Ok(None),
+      _ => println!(
+        "   {}",
+        format!("don't know what to do with {}", dotfile.name).dimmed()
+      ),
+    }
+  }
+}
+
+/// Brings broken links back, `opts` are expected to be forceful
+pub fn repair(
+  cx: &Context,
+  dotfiles: &HashMap<u32, DotFile>,
+  opts: &LinkOpts,
+  prefer: Option<Prefer>,
+  backups: &mut Backups,
+) -> Result<()> {
+  let mut chosen: Vec<&DotFile> = Vec::new();
+  let mut reports: Vec<Report> = Vec::new();
+
+  let mut ids: Vec<&u32> = dotfiles.keys().collect();
+  ids.sort_unstable();
+
+  for id in ids {
+    let dotfile = dotfiles.get(id).unwrap();
+
+    // copies are expected to drift apart, that is what `pull` is for
+    let mode = dotfile.link_mode(opts.mode);
+    if mode == LinkMode::Copy || inspect::state(dotfile, mode) != State::Diverged {
+      continue;
+    }
+
+    println!("|> {}: {}", dotfile.name.bold(), "link is broken".red());
+    print_newer(dotfile);
+
+    // a file that can't be read is told about, the rest are repaired all the same
+    if let Err(e) = print_diff(dotfile) {
+      reports.push(Report {
+        dotfile_id: *id,
+        error: Some(error::Error {
+          kind: e.kind().into(),
+          message: e.to_string(),
+          stage: error::ErrorStage::Compare,
+        }),
+      });
+      continue;
+    }
+
+    let side = match prefer {
+      Some(side) => Some(side),
+      None => ask(dotfile)?,
+    };
+
+    match side {
+      None => continue,
+      Some(Prefer::Src) => {}
+      Some(Prefer::Dst) => {
+        if let Err(e) = fs::copy(dotfile.dst_file_path(), dotfile.src_file_path()) {
+          reports.push(Report {
+            dotfile_id: *id,
+            error: Some(error::Error {
+              kind: e.kind().into(),
+              message: e.to_string(),
+              stage: error::ErrorStage::Pull,
+            }),
+          });
+          continue;
+        }
+      }
+    }
+
+    chosen.push(dotfile);
+  }
+
+  reports.extend(run(plan::plan(chosen, opts), backups, None)?);
+  reports.sort_by_key(|report| report.dotfile_id);
+
+  backups.finish()?;
+  remember(cx, dotfiles, &reports, &[], opts)?;
+
+  let mut exit = 0;
+
+  for report in &reports {
+    let dotfile = dotfiles.get(&report.dotfile_id).unwrap();
+
+    match &report.error {
+      None => println!("|> {}: {}", dotfile.name.bold(), "repaired".green()),
+      Some(err) => {
+        exit = 1;
+        Report::print(dotfile, err);
+      }
+    }
+  }
+
+  if exit == 1 {
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,162 @@
+use crate::journal::{self, Journal, Undo};
+use crate::{error, sudo};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::io::{self, prelude::*, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RollbackReport {
+  path: PathBuf,
+  error: Option<error::Error>,
+}
+
+fn undo_all(undos: Vec<Undo>) -> (Vec<RollbackReport>, Vec<Undo>) {
+  let mut reports = Vec::with_capacity(undos.len());
+  let mut denied = vec![];
+
+  for undo in undos {
+    match undo.run() {
+      Ok(_) => reports.push(RollbackReport {
+        path: undo.path().to_path_buf(),
+        error: None,
+      }),
+      Err(e) if e.kind() == ErrorKind::PermissionDenied => denied.push(undo),
+      Err(e) => reports.push(RollbackReport {
+        path: undo.path().to_path_buf(),
+        error: Some(error::Error {
+          kind: e.kind().into(),
+          message: e.to_string(),
+          stage: error::ErrorStage::Rollback,
+        }),
+      }),
+    }
+  }
+
+  (reports, denied)
+}
+
+/// Takes back everything written down in the journal, the sudo child takes care of
+/// whatever the current user is not allowed to touch. The journal is dropped once all of
+/// it has been taken back, and kept for another try otherwise. Tells which it was
+pub(super) fn roll_back(journal: Journal) -> Result<bool> {
+  let changes = Journal::read(&journal.path)?;
+  let (mut reports, denied) = undo_all(journal::undo(&changes));
+
+  if !denied.is_empty() {
+    let args = [std::env::args().next().unwrap(), "rollback".to_owned()];
+    let sreports: Vec<RollbackReport> = sudo::sudo_with(args, &denied)?;
+    reports.extend(sreports);
+  }
+
+  // taking a replaced destination back is two undos of the same path, one line is enough
+  let mut merged: Vec<RollbackReport> = Vec::with_capacity(reports.len());
+
+  for report in reports {
+    match merged.iter_mut().find(|one| one.path == report.path) {
+      Some(one) if one.error.is_none() => *one = report,
+      Some(_) => {}
+      None => merged.push(report),
+    }
+  }
+
+  let mut ok = true;
+
+  for report in merged {
+    let path = report.path.to_str().unwrap();
+
+    match report.error {
+      None => println!("|> {}: {}", path.bold(), "rolled back".green()),
+      Some(err) => {
+        ok = false;
+        println!("|> {}: {}", path.bold(), err.message.red());
+        println!("   - Error occured while trying to {}", err.stage);
+      }
+    }
+  }
+
+  match ok {
+    true => journal.close()?,
+    false => eprintln!(
+      "|> {}",
+      "not everything could be rolled back, `dtf rollback` tries again".yellow()
+    ),
+  }
+
+  Ok(ok)
+}
+
+/// Rolls back the run of `ln --atomic` that did not make it to the end
+pub fn rollback(home_dir: &Path, child: bool) -> Result<()> {
+  if child {
+    let mut request_json = String::with_capacity(256);
+    io::stdin().read_line(&mut request_json)?;
+    let undos: Vec<Undo> = serde_json::from_str(&request_json)?;
+
+    let (mut reports, denied) = undo_all(undos);
+    reports.extend(denied.into_iter().map(|undo| RollbackReport {
+      path: undo.path().to_path_buf(),
+      error: Some(error::Error {
+        kind: error::ErrorKind::PermissionDenied,
+        message: "permission denied".to_owned(),
+        stage: error::ErrorStage::Rollback,
+      }),
+    }));
+
+    println!("{}", serde_json::to_string(&reports)?);
+    return Ok(());
+  }
+
+  let path = journal::journal_path(home_dir);
+
+  if !path.exists() {
+    println!("|> {}", "there is nothing to roll back".dimmed());
+    return Ok(());
+  }
+
+  if !roll_back(Journal::at(path))? {
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
+
+/// Looks for a journal left behind by a crashed `ln --atomic` and offers to roll it back,
+/// only asking when the command at hand is about to `change` the filesystem too
+pub fn recover(home_dir: &Path, change: bool) -> Result<()> {
+  let path = journal::journal_path(home_dir);
+
+  if !path.exists() {
+    return Ok(());
+  }
+
+  eprintln!(
+    "|> {}",
+    "the last `ln --atomic` did not make it to the end".yellow()
+  );
+
+  // nobody to ask, or no need to, the journal stays until someone runs `dtf rollback`
+  if !change || unsafe { libc::isatty(0) } == 0 {
+    eprintln!("   - run `dtf rollback` to take its changes back");
+    return Ok(());
+  }
+
+  eprint!("   roll its changes back? [y/N] ");
+  io::stderr().flush()?;
+
+  let mut answer = String::with_capacity(4);
+  io::stdin().read_line(&mut answer)?;
+
+  match answer.trim() {
+    "y" | "yes" => {
+      roll_back(Journal::at(path))?;
+    }
+    // the journal stays, there is no taking the changes back without it
+    _ => eprintln!(
+      "   - {}",
+      "leaving things as they are, run `dtf rollback` to take them back later".dimmed()
+    ),
+  }
+
+  Ok(())
+}
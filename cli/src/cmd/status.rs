@@ -0,0 +1,70 @@
+use crate::inspect::{self, State};
+use colored::{ColoredString, Colorize};
+use dtflib::{Context, DotFile, LinkMode};
+use std::collections::HashMap;
+use std::io::Result;
+
+fn colorize(state: State) -> ColoredString {
+  let text = format!("{:<19}", state.to_string());
+
+  match state {
+    State::Linked => text.green(),
+    State::MissingDst | State::NeedsPrivileges => text.yellow(),
+    State::MissingSrc | State::Diverged | State::WrongTarget => text.red(),
+  }
+}
+
+/// Why a diverged destination is not linked, a hard link that lost its tie to the source
+/// may still have the very same content
+fn explain(dotfile: &DotFile) -> &'static str {
+  match inspect::is_same_content(&dotfile.src_file_path(), &dotfile.dst_file_path()) {
+    true => "same content, but not linked to the source anymore, `dtf repair` links it back",
+    false => "edited apart from the source, `dtf repair` picks a side",
+  }
+}
+
+pub fn status(
+  _cx: &Context,
+  dotfiles: &HashMap<u32, DotFile>,
+  mode: LinkMode,
+  porcelain: bool,
+) -> Result<()> {
+  let mut dotfiles: Vec<&DotFile> = dotfiles.values().collect();
+  dotfiles.sort_unstable_by_key(|dotfile| dotfile.id);
+
+  let mut exit = 0;
+
+  for dotfile in dotfiles {
+    let state = inspect::state(dotfile, dotfile.link_mode(mode));
+
+    if !state.is_linked() {
+      exit = 1;
+    }
+
+    if porcelain {
+      println!(
+        "{}\t{}\t{}",
+        state.name(),
+        dotfile.src_file_path().to_str().unwrap(),
+        dotfile.dst_file_path().to_str().unwrap()
+      );
+    } else {
+      println!(
+        "{} {} {}",
+        colorize(state),
+        dotfile.name.bold(),
+        dotfile.dst_file_path().to_str().unwrap().dimmed()
+      );
+
+      if state == State::Diverged {
+        println!("{:<19} {}", "", explain(dotfile).dimmed());
+      }
+    }
+  }
+
+  if exit == 1 {
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,146 @@
+use crate::{backup::Backups, error, inspect, sudo, Report};
+use colored::Colorize;
+use dtflib::{manifest::Record, Context, DotFile, Manifest};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::PathBuf;
+
+/// Makes sure the destination is what dtf has installed, given what the manifest
+/// remembers of it
+fn vet(dotfile: &DotFile, record: Option<&Record>) -> std::result::Result<(), error::Error> {
+  match inspect::is_installed(dotfile, record) {
+    Ok(true) => Ok(()),
+    Ok(false) => Err(error::Error {
+      kind: error::ErrorKind::Modified,
+      message: "destination file was modified, leaving it alone".to_owned(),
+      stage: error::ErrorStage::Unlink,
+    }),
+    Err(e) => Err(error::Error {
+      kind: e.kind().into(),
+      message: match e.kind() {
+        ErrorKind::NotFound => "destination file was not found".to_owned(),
+        _ => e.to_string(),
+      },
+      stage: error::ErrorStage::Unlink,
+    }),
+  }
+}
+
+fn remove_one(dotfile: &DotFile, backups: &mut Backups) -> std::result::Result<(), error::Error> {
+  let dst = &dotfile.dst_file_path();
+
+  backups.save(dst)?;
+
+  match fs::remove_file(dst) {
+    Ok(_) => Ok(()),
+    Err(e) => Err(error::Error {
+      kind: e.kind().into(),
+      message: e.to_string(),
+      stage: error::ErrorStage::RemoveFile,
+    }),
+  }
+}
+
+pub fn unlink(cx: &Context, dotfiles: &HashMap<u32, DotFile>, backups: &mut Backups) -> Result<()> {
+  let mut denied: HashMap<u32, &DotFile> = HashMap::new();
+  let mut reports: Vec<Report> = Vec::with_capacity(dotfiles.len());
+
+  // the sudo child is only handed what the main process has vetted already
+  let manifest = match cx.is_main() {
+    true => Some(Manifest::load(cx.home_dir)?),
+    false => None,
+  };
+
+  for (id, dotfile) in dotfiles {
+    let vetted = match &manifest {
+      Some(manifest) => vet(
+        dotfile,
+        manifest.get(cx.config_path, &dotfile.dst_file_path()),
+      ),
+      None => Ok(()),
+    };
+
+    match vetted.and_then(|_| remove_one(dotfile, backups)) {
+      Ok(_) => reports.push(Report {
+        dotfile_id: *id,
+        error: None,
+      }),
+      Err(e) => match (&e.kind, &e.stage) {
+        (error::ErrorKind::PermissionDenied, error::ErrorStage::RemoveFile) => {
+          denied.insert(*id, dotfile);
+        }
+        _ => reports.push(Report {
+          dotfile_id: *id,
+          error: Some(e),
+        }),
+      },
+    }
+  }
+
+  if cx.is_main() {
+    sudo::escalate(denied, &mut reports, backups, None)?;
+    backups.finish()?;
+
+    let mut manifest = manifest.unwrap();
+    let mut exit = 0;
+
+    for report in &reports {
+      let dotfile = dotfiles.get(&report.dotfile_id).unwrap();
+
+      let err = match &report.error {
+        None => {
+          manifest.forget(cx.config_path, &dotfile.dst_file_path());
+          println!("|> {}: {}", dotfile.name.bold(), "unlinked".green());
+          continue;
+        }
+        Some(err) => err,
+      };
+
+      if let error::ErrorKind::NotFound = err.kind {
+        println!("|> {}: {}", dotfile.name.bold(), "not linked".dimmed());
+        continue;
+      }
+
+      exit = 1;
+
+      Report::print(dotfile, err);
+    }
+
+    // whatever was created to hold the files is gone as soon as it is left empty
+    let dirs: Vec<PathBuf> = manifest.dirs(cx.config_path).into_iter().cloned().collect();
+
+    for dir in dirs {
+      match fs::remove_dir(&dir) {
+        Ok(_) => {
+          manifest.forget_dir(cx.config_path, &dir);
+          println!("|> {}: {}", dir.to_str().unwrap().bold(), "removed".green());
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+          manifest.forget_dir(cx.config_path, &dir);
+        }
+        // there is something else in it still, it stays for a later run
+        Err(e) if matches!(e.raw_os_error(), Some(libc::ENOTEMPTY) | Some(libc::EEXIST)) => {}
+        Err(e) => {
+          exit = 1;
+          println!(
+            "|> {}: {}",
+            dir.to_str().unwrap().bold(),
+            format!("can't be removed: {}", e).red()
+          );
+        }
+      }
+    }
+
+    manifest.save()?;
+
+    if exit == 1 {
+      std::process::exit(1);
+    }
+
+    Ok(())
+  } else {
+    sudo::respond(reports, backups);
+    Ok(())
+  }
+}
@@ -0,0 +1,32 @@
+use crate::error::{Error, ErrorStage};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub fn copy(src: &Path, dst: &Path) -> Result<(), Error> {
+  match fs::copy(src, dst) {
+    Ok(_) => Ok(()),
+    Err(e) => match e.kind() {
+      io::ErrorKind::NotFound => Err(Error {
+        kind: e.kind().into(),
+        message: "destination directory was not found".to_owned(),
+        stage: ErrorStage::Copy,
+      }),
+      _ => Err(Error {
+        kind: e.kind().into(),
+        message: e.to_string(),
+        stage: ErrorStage::Copy,
+      }),
+    },
+  }
+}
+
+/// Hex encoded sha256 of the file content
+pub fn hash_file(path: &Path) -> io::Result<String> {
+  let mut f = fs::File::open(path)?;
+  let mut hasher = Sha256::new();
+  io::copy(&mut f, &mut hasher)?;
+
+  Ok(format!("{:x}", hasher.finalize()))
+}
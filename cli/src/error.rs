@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ErrorKind {
+  NotFound,
+  PermissionDenied,
+  AlreadyExists,
+  Conflict,
+  Modified,
+  Other,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ErrorStage {
+  CreateDir,
+  RemoveFile,
+  HardLink,
+  Symlink,
+  Copy,
+  Pull,
+  Compare,
+  Unlink,
+  Adopt,
+  Backup,
+  Restore,
+  Journal,
+  Rollback,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Error {
+  pub kind: ErrorKind,
+  pub stage: ErrorStage,
+  pub message: String,
+}
+
+impl std::convert::From<std::io::ErrorKind> for ErrorKind {
+  fn from(io_err_kind: std::io::ErrorKind) -> Self {
+    match &io_err_kind {
+      std::io::ErrorKind::NotFound => Self::NotFound,
+      std::io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+      std::io::ErrorKind::AlreadyExists => Self::AlreadyExists,
+      _ => Self::Other,
+    }
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.message)
+  }
+}
+
+impl fmt::Display for ErrorStage {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ErrorStage::CreateDir => write!(f, "create destination directory"),
+      ErrorStage::RemoveFile => write!(f, "remove destination file"),
+      ErrorStage::HardLink => write!(f, "make a hard link"),
+      ErrorStage::Symlink => write!(f, "make a symbolic link"),
+      ErrorStage::Copy => write!(f, "copy the file"),
+      ErrorStage::Pull => write!(f, "pull the file back"),
+      ErrorStage::Compare => write!(f, "compare the source with the destination"),
+      ErrorStage::Unlink => write!(f, "unlink destination file"),
+      ErrorStage::Adopt => write!(f, "move the file into the repo"),
+      ErrorStage::Backup => write!(f, "back up destination file"),
+      ErrorStage::Restore => write!(f, "restore the file from a backup"),
+      ErrorStage::Journal => write!(f, "write the journal down"),
+      ErrorStage::Rollback => write!(f, "roll the change back"),
+    }
+  }
+}
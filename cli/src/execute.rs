@@ -0,0 +1,156 @@
+use crate::backup::Backups;
+use crate::copy::copy;
+use crate::error::{Error, ErrorKind, ErrorStage};
+use crate::hard_link::hard_link;
+use crate::journal::{Change, Journal};
+use crate::plan::{Action, Operation};
+use crate::symlink::symlink;
+use crate::Report;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+fn create_dir(path: &Path, mode: Option<u32>) -> io::Result<()> {
+  fs::create_dir(path)?;
+
+  // the mode given to `mkdir` itself would be cut by the umask
+  match mode {
+    Some(mode) => fs::set_permissions(path, fs::Permissions::from_mode(mode)),
+    None => Ok(()),
+  }
+}
+
+fn run(action: &Action, backups: &mut Backups) -> Result<(), Error> {
+  match action {
+    Action::CreateDir { path, mode } => create_dir(path, *mode).map_err(|e| Error {
+      kind: e.kind().into(),
+      message: e.to_string(),
+      stage: ErrorStage::CreateDir,
+    }),
+    Action::Backup { path } => backups.save(path),
+    Action::RemoveFile { path } => match fs::remove_file(path) {
+      // planned blindly when the destination couldn't be looked at
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+      res => res.map_err(|e| Error {
+        kind: e.kind().into(),
+        message: e.to_string(),
+        stage: ErrorStage::RemoveFile,
+      }),
+    },
+    Action::HardLink { src, dst } => hard_link(src, dst),
+    Action::Symlink { src, dst } => symlink(src, dst),
+    Action::Copy { src, dst } => copy(src, dst),
+    Action::Skip { .. } => Ok(()),
+  }
+}
+
+/// Runs the action, writing down what it has changed if there is a journal to write to
+fn run_journaled(
+  action: &Action,
+  backups: &mut Backups,
+  journal: Option<&Journal>,
+) -> Result<(), Error> {
+  let saved = backups.entries.len();
+
+  run(action, backups)?;
+
+  let journal = match journal {
+    Some(val) => val,
+    None => return Ok(()),
+  };
+
+  let change = match action {
+    Action::CreateDir { path, .. } => Change::CreatedDir { path: path.clone() },
+    Action::Backup { .. } => match backups.entries.get(saved) {
+      Some(entry) => Change::Saved {
+        run_dir: backups.dir.clone(),
+        entry: entry.clone(),
+      },
+      None => return Ok(()),
+    },
+    Action::RemoveFile { path } => Change::Removed { path: path.clone() },
+    Action::HardLink { dst, .. } | Action::Symlink { dst, .. } | Action::Copy { dst, .. } => {
+      Change::Created { path: dst.clone() }
+    }
+    Action::Skip { .. } => return Ok(()),
+  };
+
+  journal.write(&change).map_err(|e| Error {
+    kind: e.kind().into(),
+    message: e.to_string(),
+    stage: ErrorStage::Journal,
+  })
+}
+
+/// Runs the operations in order, reporting on every dotfile they belong to. Once an
+/// operation fails, the rest of its dotfile operations are dropped. Unless `escalate` is
+/// off, a lack of permissions is not a failure: the operation is handed back along with
+/// the rest of its dotfile operations, so that the sudo child could pick them up.
+/// With a `journal`, every change is written down and the first failure stops the run
+pub fn execute(
+  operations: &[Operation],
+  backups: &mut Backups,
+  escalate: bool,
+  journal: Option<&Journal>,
+) -> (Vec<Report>, HashMap<u32, Vec<Operation>>) {
+  let mut ids: Vec<u32> = Vec::new();
+  let mut reports: Vec<Report> = Vec::new();
+  let mut failed: HashSet<u32> = HashSet::new();
+  let mut denied: HashMap<u32, Vec<Operation>> = HashMap::new();
+
+  for op in operations {
+    let id = op.dotfile_id;
+
+    if ids.last() != Some(&id) {
+      ids.push(id);
+    }
+
+    if failed.contains(&id) {
+      continue;
+    }
+
+    if let Some(rest) = denied.get_mut(&id) {
+      rest.push(op.clone());
+      continue;
+    }
+
+    match run_journaled(&op.action, backups, journal) {
+      Ok(_) => {}
+      Err(e) => match e.kind {
+        ErrorKind::PermissionDenied if escalate => {
+          denied.insert(
+            id,
+            vec![Operation {
+              privileged: true,
+              ..op.clone()
+            }],
+          );
+        }
+        _ => {
+          failed.insert(id);
+          reports.push(Report {
+            dotfile_id: id,
+            error: Some(e),
+          });
+
+          if journal.is_some() {
+            break;
+          }
+        }
+      },
+    }
+  }
+
+  for id in ids {
+    if !failed.contains(&id) && !denied.contains_key(&id) {
+      reports.push(Report {
+        dotfile_id: id,
+        error: None,
+      });
+    }
+  }
+
+  (reports, denied)
+}
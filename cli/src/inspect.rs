@@ -0,0 +1,209 @@
+use crate::copy::hash_file;
+use dtflib::{manifest::Record, DotFile, LinkMode};
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path, PathBuf};
+use std::{fmt, fs, io};
+
+/// Whether both paths lead to the very same inode
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+  match (fs::metadata(a), fs::metadata(b)) {
+    (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+    _ => false,
+  }
+}
+
+/// Whether `link` is a symbolic link that resolves to the `target`
+pub fn is_symlink_to(link: &Path, target: &Path) -> bool {
+  match fs::symlink_metadata(link) {
+    Ok(meta) if meta.file_type().is_symlink() => {}
+    _ => return false,
+  }
+
+  match (fs::canonicalize(link), fs::canonicalize(target)) {
+    (Ok(a), Ok(b)) => a == b,
+    _ => false,
+  }
+}
+
+/// Whether both paths are regular files with the same content
+pub fn is_same_content(a: &Path, b: &Path) -> bool {
+  match (hash_file(a), hash_file(b)) {
+    (Ok(a), Ok(b)) => a == b,
+    _ => false,
+  }
+}
+
+/// Whether the destination is still exactly what dtf would have put there: a hard link to
+/// the source or a symlink to it. A copy has no tie to the source, so it only counts when
+/// its `record` says it was copied and its content hasn't changed since. Fails with
+/// `NotFound` when there is no destination at all
+pub fn is_installed(dotfile: &DotFile, record: Option<&Record>) -> io::Result<bool> {
+  let src = &dotfile.src_file_path();
+  let dst = &dotfile.dst_file_path();
+
+  let meta = fs::symlink_metadata(dst)?;
+
+  if meta.file_type().is_symlink() {
+    return Ok(is_symlink_to(dst, src));
+  }
+
+  if is_same_file(src, dst) {
+    return Ok(true);
+  }
+
+  match record {
+    Some(record) if record.strategy == LinkMode::Copy => {
+      Ok(record.hash.as_deref() == Some(hash_file(dst)?.as_str()))
+    }
+    _ => Ok(false),
+  }
+}
+
+/// Resolves `..` and `.` without touching the filesystem, the path may not exist anymore
+fn normalize(path: &Path) -> PathBuf {
+  let mut p = PathBuf::new();
+
+  for one in path.components() {
+    match one {
+      Component::ParentDir => {
+        p.pop();
+      }
+      Component::CurDir => {}
+      one => p.push(one),
+    }
+  }
+
+  p
+}
+
+/// Whether the destination of the record is still exactly what dtf has installed there,
+/// even if the source is long gone. Fails with `NotFound` when there is no destination
+pub fn is_recorded(record: &Record) -> io::Result<bool> {
+  let meta = fs::symlink_metadata(&record.dst)?;
+
+  match record.strategy {
+    LinkMode::HardLink => Ok(!meta.file_type().is_symlink() && Some(meta.ino()) == record.inode),
+    LinkMode::Symlink => {
+      if !meta.file_type().is_symlink() {
+        return Ok(false);
+      }
+
+      let target = fs::read_link(&record.dst)?;
+      let target = match record.dst.parent() {
+        Some(dir) => dir.join(target),
+        None => target,
+      };
+
+      Ok(normalize(&target) == normalize(&record.src))
+    }
+    LinkMode::Copy => {
+      if !meta.file_type().is_file() {
+        return Ok(false);
+      }
+
+      Ok(record.hash.as_deref() == Some(hash_file(&record.dst)?.as_str()))
+    }
+  }
+}
+
+/// Link state of a single dotfile as it is seen on the filesystem right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+  /// Destination is the same inode as the source, a symlink to it or an untouched copy
+  Linked,
+  /// There is nothing at the destination
+  MissingDst,
+  /// There is nothing to link in the repo
+  MissingSrc,
+  /// Destination is a regular file on its own, even one with the content of the source
+  /// when it is meant to be linked rather than copied
+  Diverged,
+  /// Destination is a symlink that points somewhere else
+  WrongTarget,
+  /// Destination can't be inspected or created without additional permissions
+  NeedsPrivileges,
+}
+
+impl State {
+  pub fn is_linked(&self) -> bool {
+    *self == State::Linked
+  }
+
+  /// Stable name of the state, used in the machine readable output
+  pub fn name(&self) -> &'static str {
+    match self {
+      State::Linked => "linked",
+      State::MissingDst => "missing-dst",
+      State::MissingSrc => "missing-src",
+      State::Diverged => "diverged",
+      State::WrongTarget => "wrong-target",
+      State::NeedsPrivileges => "needs-privileges",
+    }
+  }
+}
+
+impl fmt::Display for State {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      State::Linked => write!(f, "linked"),
+      State::MissingDst => write!(f, "missing destination"),
+      State::MissingSrc => write!(f, "missing source"),
+      State::Diverged => write!(f, "diverged"),
+      State::WrongTarget => write!(f, "wrong target"),
+      State::NeedsPrivileges => write!(f, "needs privileges"),
+    }
+  }
+}
+
+/// Whether the current user may create and remove files within the `dir`
+pub fn is_writable(dir: &Path) -> bool {
+  let dir = match CString::new(dir.as_os_str().as_bytes()) {
+    Ok(val) => val,
+    Err(_) => return false,
+  };
+
+  unsafe { libc::access(dir.as_ptr(), libc::W_OK) == 0 }
+}
+
+/// Classifies the dotfile given the link mode it would be linked with
+pub fn state(dotfile: &DotFile, mode: LinkMode) -> State {
+  let src = &dotfile.src_file_path();
+  let dst = &dotfile.dst_file_path();
+
+  let meta = match fs::symlink_metadata(dst) {
+    Ok(meta) => meta,
+    Err(e) if e.kind() == io::ErrorKind::PermissionDenied => return State::NeedsPrivileges,
+    Err(_) => {
+      if !src.exists() {
+        return State::MissingSrc;
+      }
+
+      return match dst.parent() {
+        Some(dir) if dir.exists() && !is_writable(dir) => State::NeedsPrivileges,
+        _ => State::MissingDst,
+      };
+    }
+  };
+
+  if !src.exists() {
+    return State::MissingSrc;
+  }
+
+  if meta.file_type().is_symlink() {
+    return match is_symlink_to(dst, src) {
+      true => State::Linked,
+      false => State::WrongTarget,
+    };
+  }
+
+  if is_same_file(src, dst) {
+    return State::Linked;
+  }
+
+  match mode {
+    LinkMode::Copy if is_same_content(src, dst) => State::Linked,
+    _ => State::Diverged,
+  }
+}
@@ -0,0 +1,203 @@
+use crate::backup::{self, Entry};
+use dtflib::xdg;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, prelude::*};
+use std::path::{Path, PathBuf};
+
+/// A single filesystem mutation made by `ln --atomic`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Change {
+  /// Destination was saved into the backups of the run, it is about to be removed
+  Saved {
+    run_dir: PathBuf,
+    entry: Entry,
+  },
+  Removed {
+    path: PathBuf,
+  },
+  Created {
+    path: PathBuf,
+  },
+  CreatedDir {
+    path: PathBuf,
+  },
+}
+
+/// What has to be done to take a single change back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Undo {
+  Remove { path: PathBuf },
+  RemoveDir { path: PathBuf },
+  Restore { run_dir: PathBuf, entry: Entry },
+}
+
+impl Undo {
+  pub fn path(&self) -> &Path {
+    match self {
+      Undo::Remove { path } | Undo::RemoveDir { path } => path,
+      Undo::Restore { entry, .. } => &entry.path,
+    }
+  }
+
+  pub fn run(&self) -> io::Result<()> {
+    let res = match self {
+      Undo::Remove { path } => fs::remove_file(path),
+      Undo::RemoveDir { path } => fs::remove_dir(path),
+      Undo::Restore { run_dir, entry } => return backup::restore(run_dir, entry),
+    };
+
+    // whatever is not there anymore has nothing to take back
+    match res {
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+      res => res,
+    }
+  }
+}
+
+pub fn journal_path(home_dir: &Path) -> PathBuf {
+  xdg::state_dir(home_dir).join("journal.jsonl")
+}
+
+/// Append-only log of the changes of a single run, one JSON line per change. It only goes
+/// away once the run is over, so a leftover journal means the run has crashed
+#[derive(Debug)]
+pub struct Journal {
+  pub path: PathBuf,
+}
+
+impl Journal {
+  /// Starts a new journal, fails with `AlreadyExists` while the one of a crashed run is
+  /// still there, it has yet to be rolled back
+  pub fn begin(home_dir: &Path) -> io::Result<Self> {
+    let path = journal_path(home_dir);
+
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::OpenOptions::new()
+      .write(true)
+      .create_new(true)
+      .open(&path)?;
+
+    Ok(Self { path })
+  }
+
+  /// Journal of a run that has been started by someone else, i.e. the main process
+  pub fn at(path: PathBuf) -> Self {
+    Self { path }
+  }
+
+  pub fn write(&self, change: &Change) -> io::Result<()> {
+    let mut f = fs::OpenOptions::new().append(true).open(&self.path)?;
+
+    writeln!(f, "{}", serde_json::to_string(change)?)?;
+    f.sync_data()
+  }
+
+  /// Reads the changes back, a line cut short by a crash is ignored
+  pub fn read(path: &Path) -> io::Result<Vec<Change>> {
+    let f = fs::File::open(path)?;
+
+    Ok(
+      io::BufReader::new(f)
+        .lines()
+        .map_while(|line| line.ok())
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect(),
+    )
+  }
+
+  /// The run is over, nothing will have to be rolled back
+  pub fn close(self) -> io::Result<()> {
+    fs::remove_file(&self.path)
+  }
+}
+
+/// Turns the changes into what has to be done to take them back, the latest first
+pub fn undo(changes: &[Change]) -> Vec<Undo> {
+  let mut undos = Vec::with_capacity(changes.len());
+
+  for (i, change) in changes.iter().enumerate().rev() {
+    match change {
+      Change::Saved { .. } => {}
+      Change::Created { path } => undos.push(Undo::Remove { path: path.clone() }),
+      Change::CreatedDir { path } => undos.push(Undo::RemoveDir { path: path.clone() }),
+      Change::Removed { path } => {
+        let saved = changes[..i].iter().rev().find_map(|one| match one {
+          Change::Saved { run_dir, entry } if &entry.path == path => Some(Undo::Restore {
+            run_dir: run_dir.clone(),
+            entry: entry.clone(),
+          }),
+          _ => None,
+        });
+
+        // `ln` never removes anything it has not saved first
+        if let Some(undo) = saved {
+          undos.push(undo);
+        }
+      }
+    }
+  }
+
+  undos
+}
+
+#[cfg(test)]
+mod test_undo {
+  use super::{undo, Change, Undo};
+  use crate::backup::Entry;
+  use std::path::PathBuf;
+
+  fn entry(path: &str) -> Entry {
+    Entry {
+      path: PathBuf::from(path),
+      stored: PathBuf::from("files").join(&path[1..]),
+      mode: 0o644,
+      uid: 1000,
+      gid: 1000,
+      symlink: None,
+    }
+  }
+
+  #[test]
+  fn should_take_changes_back_in_reverse_order() {
+    let changes = vec![
+      Change::CreatedDir {
+        path: PathBuf::from("/home/gman/.config"),
+      },
+      Change::Created {
+        path: PathBuf::from("/home/gman/.config/one"),
+      },
+      Change::Saved {
+        run_dir: PathBuf::from("/run"),
+        entry: entry("/home/gman/two"),
+      },
+      Change::Removed {
+        path: PathBuf::from("/home/gman/two"),
+      },
+      Change::Created {
+        path: PathBuf::from("/home/gman/two"),
+      },
+    ];
+
+    let actual: Vec<String> = undo(&changes)
+      .iter()
+      .map(|one| match one {
+        Undo::Remove { path } => format!("rm {}", path.display()),
+        Undo::RemoveDir { path } => format!("rmdir {}", path.display()),
+        Undo::Restore { run_dir, entry } => {
+          format!("restore {} {}", run_dir.display(), entry.path.display())
+        }
+      })
+      .collect();
+
+    assert_eq!(
+      actual,
+      vec![
+        "rm /home/gman/two",
+        "restore /run /home/gman/two",
+        "rm /home/gman/.config/one",
+        "rmdir /home/gman/.config",
+      ]
+    );
+  }
+}
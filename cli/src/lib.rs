@@ -1,4 +1,4 @@
-use dtflib::client_os;
+use dtflib::{client_os, dotfile::parse_mode, LinkMode, Version};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -19,9 +19,80 @@ pub enum Cli {
 
     #[structopt(
       long,
-      parse(from_str = client_os::Type::from),
+      help = "removes what was linked before but is not in the config anymore"
     )]
-    os: Option<client_os::Type>,
+    prune: bool,
+
+    #[structopt(long, help = "only prints what would be done")]
+    dry_run: bool,
+
+    #[structopt(long, help = "takes everything back if anything fails")]
+    atomic: bool,
+
+    #[structopt(long, help = "creates missing destination directories")]
+    create_dirs: bool,
+
+    #[structopt(
+      long,
+      parse(try_from_str = parse_mode),
+      help = "permissions of the created destination directories, in octal"
+    )]
+    dir_mode: Option<u32>,
+
+    #[structopt(flatten)]
+    link: LinkArgs,
+
+    #[structopt(flatten)]
+    machine: MachineArgs,
+  },
+
+  #[structopt(name = "adopt")]
+  Adopt {
+    #[structopt(
+      name = "config-path",
+      parse(from_os_str),
+      help = "config to add the file to, it is written back without its comments"
+    )]
+    config: PathBuf,
+
+    #[structopt(name = "path", parse(from_os_str))]
+    path: PathBuf,
+
+    #[structopt(
+      long,
+      help = "target directory to put the file in, the current OS by default"
+    )]
+    target: Option<String>,
+
+    #[structopt(long, help = "index of the section to append the file to")]
+    section: Option<usize>,
+
+    #[structopt(flatten)]
+    link: LinkArgs,
+
+    #[structopt(flatten)]
+    machine: MachineArgs,
+  },
+
+  #[structopt(name = "prune")]
+  Prune {
+    #[structopt(name = "config-path", parse(from_os_str))]
+    config: PathBuf,
+
+    #[structopt(long, help = "only prints what would be removed")]
+    dry_run: bool,
+
+    #[structopt(flatten)]
+    machine: MachineArgs,
+  },
+
+  #[structopt(name = "unlink")]
+  Unlink {
+    #[structopt(name = "config-path", parse(from_os_str))]
+    config: PathBuf,
+
+    #[structopt(flatten)]
+    machine: MachineArgs,
   },
 
   #[structopt(name = "ls")]
@@ -29,19 +100,163 @@ pub enum Cli {
     #[structopt(name = "config-path", parse(from_os_str))]
     config: PathBuf,
 
+    #[structopt(flatten)]
+    link: LinkArgs,
+
+    #[structopt(flatten)]
+    machine: MachineArgs,
+
+    #[structopt(
+      long,
+      help = "shows which source directory each file is taken from and why, and why blocks are skipped"
+    )]
+    explain: bool,
+  },
+
+  #[structopt(name = "status")]
+  Status {
+    #[structopt(name = "config-path", parse(from_os_str))]
+    config: PathBuf,
+
+    #[structopt(long, help = "prints one `state<TAB>from<TAB>to` line per file")]
+    porcelain: bool,
+
+    #[structopt(flatten)]
+    mode: ModeArgs,
+
+    #[structopt(flatten)]
+    machine: MachineArgs,
+  },
+
+  #[structopt(name = "repair")]
+  Repair {
+    #[structopt(name = "config-path", parse(from_os_str))]
+    config: PathBuf,
+
     #[structopt(
       long,
-      parse(from_str = client_os::Type::from),
+      possible_values = Prefer::variants(),
+      help = "which side to keep without asking"
     )]
-    os: Option<client_os::Type>,
+    prefer: Option<Prefer>,
+
+    #[structopt(flatten)]
+    link: LinkArgs,
+
+    #[structopt(flatten)]
+    machine: MachineArgs,
+  },
+
+  #[structopt(name = "pull")]
+  Pull {
+    #[structopt(name = "config-path", parse(from_os_str))]
+    config: PathBuf,
+
+    #[structopt(flatten)]
+    mode: ModeArgs,
+
+    #[structopt(flatten)]
+    machine: MachineArgs,
   },
 
+  #[structopt(name = "backups")]
+  Backups(BackupsCmd),
+
+  #[structopt(name = "restore")]
+  Restore {
+    #[structopt(name = "run-id")]
+    run_id: String,
+
+    #[structopt(
+      name = "paths",
+      parse(from_os_str),
+      help = "only restore these files, all of the run otherwise"
+    )]
+    paths: Vec<PathBuf>,
+  },
+
+  #[structopt(name = "rollback")]
+  Rollback,
+
   Show {
     #[structopt(name = "config-path", parse(from_os_str))]
     config: PathBuf,
   },
 }
 
+// Stands in for the facts of the machine, for every command that maps the config. Plain
+// comments, a doc comment would end up as the `about` of the commands it is flattened in
+#[derive(Debug, Default, StructOpt)]
+pub struct MachineArgs {
+  #[structopt(
+    long,
+    parse(from_str = client_os::Type::from),
+  )]
+  pub os: Option<client_os::Type>,
+
+  #[structopt(long, help = "pretends the OS is of this version, e.g. `22.04`")]
+  pub os_version: Option<Version>,
+
+  #[structopt(long, help = "pretends to be the machine of this hostname")]
+  pub host: Option<String>,
+
+  #[structopt(long, help = "pretends to be run by this user")]
+  pub user: Option<String>,
+
+  #[structopt(
+    long,
+    help = "pretends to run on this CPU architecture, e.g. `aarch64`"
+  )]
+  pub arch: Option<String>,
+}
+
+// How files that do not specify their own `link` reach their destination
+#[derive(Debug, StructOpt)]
+pub struct ModeArgs {
+  #[structopt(
+    long,
+    default_value = "hardlink",
+    possible_values = LinkMode::variants(),
+    help = "how to link files that do not specify their own `link`"
+  )]
+  pub mode: LinkMode,
+}
+
+// How files are linked, for the commands that link them
+#[derive(Debug, StructOpt)]
+pub struct LinkArgs {
+  #[structopt(flatten)]
+  pub mode: ModeArgs,
+
+  #[structopt(
+    long,
+    help = "makes symbolic links relative to the destination directory"
+  )]
+  pub relative: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum BackupsCmd {
+  #[structopt(name = "list")]
+  List,
+}
+
+impl Cli {
+  /// Whether the command changes the filesystem, rather than only looking at it
+  pub fn changes_files(&self) -> bool {
+    matches!(
+      self,
+      Cli::Link { .. }
+        | Cli::Adopt { .. }
+        | Cli::Prune { .. }
+        | Cli::Unlink { .. }
+        | Cli::Repair { .. }
+        | Cli::Pull { .. }
+        | Cli::Restore { .. }
+    )
+  }
+}
+
 pub struct App;
 
 impl App {
@@ -55,8 +270,26 @@ pub use cmd::*;
 
 mod sudo;
 use sudo::sudo;
+pub use sudo::Request;
 
 mod report;
 use report::Report;
 
+mod backup;
+pub use backup::Backups;
+
+mod error;
+
 mod hard_link;
+
+mod symlink;
+
+mod copy;
+
+pub mod plan;
+
+mod execute;
+
+mod journal;
+
+mod inspect;
@@ -0,0 +1,291 @@
+use crate::error::{Error, ErrorStage};
+use crate::inspect::{is_same_content, is_same_file, is_writable};
+use crate::symlink::relative_path;
+use crate::{LinkOpts, Report};
+use colored::Colorize;
+use dtflib::{DotFile, LinkMode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A single step towards a linked dotfile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Action {
+  CreateDir {
+    path: PathBuf,
+    mode: Option<u32>,
+  },
+  Backup {
+    path: PathBuf,
+  },
+  RemoveFile {
+    path: PathBuf,
+  },
+  HardLink {
+    src: PathBuf,
+    dst: PathBuf,
+  },
+  /// `src` is what the link will contain, it may be relative to the `dst` directory
+  Symlink {
+    src: PathBuf,
+    dst: PathBuf,
+  },
+  Copy {
+    src: PathBuf,
+    dst: PathBuf,
+  },
+  Skip {
+    reason: String,
+  },
+}
+
+impl fmt::Display for Action {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Action::CreateDir {
+        path,
+        mode: Some(mode),
+      } => write!(f, "create directory {} ({:o})", path.display(), mode),
+      Action::CreateDir { path, mode: None } => write!(f, "create directory {}", path.display()),
+      Action::Backup { path } => write!(f, "back up {}", path.display()),
+      Action::RemoveFile { path } => write!(f, "remove {}", path.display()),
+      Action::HardLink { src, dst } => {
+        write!(f, "hard link {} to {}", dst.display(), src.display())
+      }
+      Action::Symlink { src, dst } => {
+        write!(f, "symlink {} to {}", dst.display(), src.display())
+      }
+      Action::Copy { src, dst } => write!(f, "copy {} to {}", src.display(), dst.display()),
+      Action::Skip { reason } => write!(f, "skip, {}", reason),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+  pub dotfile_id: u32,
+  pub action: Action,
+  /// Whether the operation has to be run by the sudo child
+  pub privileged: bool,
+}
+
+/// Everything `ln` is going to do, decided before anything is touched
+#[derive(Debug, Default)]
+pub struct Plan {
+  /// Operations of all the dotfiles that can be linked, ordered by dotfile id
+  pub operations: Vec<Operation>,
+  /// Dotfiles that can't be linked at all, with the reason why
+  pub reports: Vec<Report>,
+}
+
+impl Plan {
+  /// Prints the plan grouped by dotfiles, as `ln --dry-run` shows it
+  pub fn print(&self, dotfiles: &HashMap<u32, DotFile>) {
+    let mut last: Option<u32> = None;
+
+    for op in &self.operations {
+      if last != Some(op.dotfile_id) {
+        let dotfile = dotfiles.get(&op.dotfile_id).unwrap();
+        println!("|> {}", dotfile.name.bold());
+        last = Some(op.dotfile_id);
+      }
+
+      match (&op.action, op.privileged) {
+        (Action::Skip { .. }, _) => println!("   - {}", op.action.to_string().dimmed()),
+        (_, true) => println!("   - {} {}", op.action, "(sudo)".yellow()),
+        (_, false) => println!("   - {}", op.action),
+      }
+    }
+
+    for report in &self.reports {
+      let dotfile = dotfiles.get(&report.dotfile_id).unwrap();
+      Report::print(dotfile, report.error.as_ref().unwrap());
+    }
+  }
+}
+
+fn stage(mode: LinkMode) -> ErrorStage {
+  match mode {
+    LinkMode::HardLink => ErrorStage::HardLink,
+    LinkMode::Symlink => ErrorStage::Symlink,
+    LinkMode::Copy => ErrorStage::Copy,
+  }
+}
+
+/// Whether the destination is already linked exactly the way the `link` action would do it
+fn is_done(link: &Action, dst_meta: &fs::Metadata) -> bool {
+  match link {
+    Action::HardLink { src, dst } => !dst_meta.file_type().is_symlink() && is_same_file(src, dst),
+    Action::Symlink { src, dst } => {
+      dst_meta.file_type().is_symlink() && fs::read_link(dst).is_ok_and(|val| &val == src)
+    }
+    Action::Copy { src, dst } => {
+      dst_meta.file_type().is_file() && !is_same_file(src, dst) && is_same_content(src, dst)
+    }
+    _ => false,
+  }
+}
+
+/// The closest ancestor of the `dir` that exists, the `dir` itself included
+fn existing_ancestor(dir: &Path) -> &Path {
+  dir
+    .ancestors()
+    .find(|one| fs::symlink_metadata(one).is_ok())
+    .unwrap_or_else(|| Path::new("/"))
+}
+
+fn plan_one(
+  dotfile: &DotFile,
+  opts: &LinkOpts,
+  planned_dirs: &mut HashSet<PathBuf>,
+) -> Result<Vec<Operation>, Error> {
+  let mode = dotfile.link_mode(opts.mode);
+  let src = dotfile.src_file_path();
+  let dst = dotfile.dst_file_path();
+  let dir = dst.parent().unwrap_or_else(|| Path::new("/"));
+
+  // unlike hard links, a symlink to nowhere is perfectly valid for the OS
+  if !src.exists() {
+    return Err(Error {
+      kind: io::ErrorKind::NotFound.into(),
+      message: "source file was not found".to_owned(),
+      stage: stage(mode),
+    });
+  }
+
+  // whatever is created for the files planned before doesn't need to be created again
+  let existing = existing_ancestor(dir);
+  let mut missing: Vec<PathBuf> = dir
+    .ancestors()
+    .take_while(|one| *one != existing)
+    .filter(|one| !planned_dirs.contains(*one))
+    .map(|one| one.to_path_buf())
+    .collect();
+  missing.reverse();
+
+  if !missing.is_empty() && !dotfile.creates_dirs(opts.create_dirs) {
+    return Err(Error {
+      kind: io::ErrorKind::NotFound.into(),
+      message: "destination directory was not found".to_owned(),
+      stage: stage(mode),
+    });
+  }
+
+  let link = match mode {
+    LinkMode::HardLink => Action::HardLink {
+      src,
+      dst: dst.clone(),
+    },
+    LinkMode::Symlink => Action::Symlink {
+      src: match dotfile.is_relative(opts.relative) {
+        true => relative_path(&src, dir),
+        false => src,
+      },
+      dst: dst.clone(),
+    },
+    LinkMode::Copy => Action::Copy {
+      src,
+      dst: dst.clone(),
+    },
+  };
+
+  let privileged = !is_writable(existing);
+  let op = |action: Action| Operation {
+    dotfile_id: dotfile.id,
+    action,
+    privileged,
+  };
+
+  if !missing.is_empty() {
+    let dir_mode = dotfile.dir_mode(opts.dir_mode);
+    let mut ops: Vec<Operation> = missing
+      .into_iter()
+      .map(|path| {
+        planned_dirs.insert(path.clone());
+        op(Action::CreateDir {
+          path,
+          mode: dir_mode,
+        })
+      })
+      .collect();
+    ops.push(op(link));
+
+    return Ok(ops);
+  }
+
+  let meta = match fs::symlink_metadata(&dst) {
+    Ok(meta) => meta,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![op(link)]),
+    // only the sudo child is able to look, so whatever may be there is its to deal with
+    Err(_) => {
+      let op = |action: Action| Operation {
+        dotfile_id: dotfile.id,
+        action,
+        privileged: true,
+      };
+
+      return Ok(match opts.force {
+        true => vec![
+          op(Action::Backup { path: dst.clone() }),
+          op(Action::RemoveFile { path: dst }),
+          op(link),
+        ],
+        false => vec![op(link)],
+      });
+    }
+  };
+
+  if is_done(&link, &meta) {
+    return Ok(vec![op(Action::Skip {
+      reason: "already linked".to_owned(),
+    })]);
+  }
+
+  if !opts.force {
+    return Err(Error {
+      kind: io::ErrorKind::AlreadyExists.into(),
+      message: "destination file already exists".to_owned(),
+      stage: stage(mode),
+    });
+  }
+
+  // a hard link to the source holds nothing the source doesn't, so there is nothing to save
+  if !meta.file_type().is_symlink() && is_same_file(&dotfile.src_file_path(), &dst) {
+    return Ok(vec![op(Action::RemoveFile { path: dst }), op(link)]);
+  }
+
+  // writing through a symlink or a hard link would overwrite the source itself,
+  // so the destination always goes away first
+  Ok(vec![
+    op(Action::Backup { path: dst.clone() }),
+    op(Action::RemoveFile { path: dst }),
+    op(link),
+  ])
+}
+
+/// Decides what has to be done to link the dotfiles, given what is on the filesystem
+pub fn plan<'a, I>(dotfiles: I, opts: &LinkOpts) -> Plan
+where
+  I: IntoIterator<Item = &'a DotFile<'a>>,
+{
+  let mut dotfiles: Vec<&DotFile> = dotfiles.into_iter().collect();
+  dotfiles.sort_unstable_by_key(|dotfile| dotfile.id);
+
+  let mut plan = Plan::default();
+  let mut planned_dirs: HashSet<PathBuf> = HashSet::new();
+
+  for dotfile in dotfiles {
+    match plan_one(dotfile, opts, &mut planned_dirs) {
+      Ok(operations) => plan.operations.extend(operations),
+      Err(e) => plan.reports.push(Report {
+        dotfile_id: dotfile.id,
+        error: Some(e),
+      }),
+    }
+  }
+
+  plan
+}
@@ -1,4 +1,4 @@
-use crate::hard_link;
+use crate::error;
 use colored::Colorize;
 use dtflib::DotFile;
 use serde::{Deserialize, Serialize};
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Report {
   pub dotfile_id: u32,
-  pub error: Option<hard_link::Error>,
+  pub error: Option<error::Error>,
 }
 
 impl Report {
@@ -14,11 +14,14 @@ impl Report {
     self.error.is_none()
   }
 
-  pub fn print(dotfile: &DotFile, err: &hard_link::Error) {
+  pub fn print(dotfile: &DotFile, err: &error::Error) {
     println!("|> {}: {}", dotfile.name.bold(), err.message.red());
     println!("   - Error occured while trying to {}", err.stage);
     match err.stage {
-      hard_link::ErrorStage::HardLink => {
+      error::ErrorStage::HardLink
+      | error::ErrorStage::Symlink
+      | error::ErrorStage::Copy
+      | error::ErrorStage::Compare => {
         println!(
           "     from: {}",
           dotfile
@@ -38,7 +41,33 @@ impl Report {
             .dimmed()
         );
       }
-      hard_link::ErrorStage::RemoveFile => {
+      error::ErrorStage::Pull | error::ErrorStage::Adopt => {
+        println!(
+          "     from: {}",
+          dotfile
+            .dst_file_path()
+            .as_os_str()
+            .to_str()
+            .unwrap()
+            .dimmed()
+        );
+        println!(
+          "     to  : {}",
+          dotfile
+            .src_file_path()
+            .as_os_str()
+            .to_str()
+            .unwrap()
+            .dimmed()
+        );
+      }
+      error::ErrorStage::CreateDir
+      | error::ErrorStage::RemoveFile
+      | error::ErrorStage::Unlink
+      | error::ErrorStage::Backup
+      | error::ErrorStage::Restore
+      | error::ErrorStage::Journal
+      | error::ErrorStage::Rollback => {
         println!(
           "     {}",
           dotfile
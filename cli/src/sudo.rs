@@ -1,24 +1,122 @@
-use crate::Report;
-use dtflib::{DotFile, CHILD_PARAM};
+use crate::{backup::Backups, journal::Journal, Report};
+use dtflib::CHILD_PARAM;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::prelude::{Read, Write};
 use std::io::Result;
+use std::path::PathBuf;
 use std::process::{Command, Stdio};
 
-pub fn sudo<'a>(dotfiles: &HashMap<u32, &DotFile>) -> Result<Vec<Report>> {
+/// What the main process hands over to the sudo child through its stdin
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Request<D> {
+  /// Run directory of the main process backups, the child saves its backups there too
+  pub backup_dir: PathBuf,
+  /// Uid and gid of the main process while the run directory is yet to be created, the
+  /// child creates it for them on its first backup
+  #[serde(default)]
+  pub backup_owner: Option<(u32, u32)>,
+  /// Journal of the main process if it has one, the child writes its changes there too
+  #[serde(default)]
+  pub journal: Option<PathBuf>,
+  /// Either the dotfiles themselves or the operations planned for them, by dotfile id
+  pub dotfiles: HashMap<u32, D>,
+}
+
+/// What the sudo child prints back to the main process
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Response {
+  pub reports: Vec<Report>,
+  pub backups: Vec<crate::backup::Entry>,
+  /// The run directory, if the child is the one that has created it
+  #[serde(default)]
+  pub backup_dir: Option<PathBuf>,
+}
+
+/// Runs the same command again with additional permissions, feeding it the `input`
+/// and reading whatever it responds with
+pub fn sudo<I: Serialize, O: DeserializeOwned>(input: &I) -> Result<O> {
+  sudo_with(std::env::args(), input)
+}
+
+/// Same as `sudo`, but runs the command given by `args` instead of the current one
+pub fn sudo_with<A, I, O>(args: A, input: &I) -> Result<O>
+where
+  A: IntoIterator,
+  A::Item: AsRef<std::ffi::OsStr>,
+  I: Serialize,
+  O: DeserializeOwned,
+{
   let sudo = Command::new("sudo")
-    .args(std::env::args())
+    .args(args)
     .arg(CHILD_PARAM)
     .stdin(Stdio::piped())
     .stdout(Stdio::piped())
     .spawn()?;
 
-  let dotfiles_json = serde_json::to_string(dotfiles)?;
-  sudo.stdin.unwrap().write_all(dotfiles_json.as_bytes())?;
+  let input_json = serde_json::to_string(input)?;
+  sudo.stdin.unwrap().write_all(input_json.as_bytes())?;
+
+  let mut output_json = String::new();
+  sudo.stdout.unwrap().read_to_string(&mut output_json)?;
+  let output: O = serde_json::from_str(&output_json)?;
+
+  Ok(output)
+}
+
+/// Hands the denied dotfiles, or their operations, over to the sudo child, collecting
+/// whatever it has done
+pub fn escalate<D: Serialize>(
+  denied: HashMap<u32, D>,
+  reports: &mut Vec<Report>,
+  backups: &mut Backups,
+  journal: Option<&Journal>,
+) -> Result<()> {
+  escalate_with(std::env::args(), denied, reports, backups, journal)
+}
+
+/// Same as `escalate`, but runs the command given by `args` instead of the current one
+pub fn escalate_with<A, D>(
+  args: A,
+  denied: HashMap<u32, D>,
+  reports: &mut Vec<Report>,
+  backups: &mut Backups,
+  journal: Option<&Journal>,
+) -> Result<()>
+where
+  A: IntoIterator,
+  A::Item: AsRef<std::ffi::OsStr>,
+  D: Serialize,
+{
+  if denied.is_empty() {
+    return Ok(());
+  }
 
-  let mut reports_json = String::new();
-  sudo.stdout.unwrap().read_to_string(&mut reports_json)?;
-  let reports: Vec<Report> = serde_json::from_str(&reports_json).unwrap();
+  backups.prepare()?;
+
+  let response: Response = sudo_with(
+    args,
+    &Request {
+      backup_dir: backups.dir.clone(),
+      backup_owner: backups.owner(),
+      journal: journal.map(|val| val.path.clone()),
+      dotfiles: denied,
+    },
+  )?;
+
+  reports.extend(response.reports);
+  backups.extend(response.backup_dir, response.backups);
+
+  Ok(())
+}
 
-  Ok(reports)
+/// Prints the outcome of the sudo child for the main process to read
+pub fn respond(reports: Vec<Report>, backups: &Backups) {
+  let stdout = serde_json::to_string(&Response {
+    reports,
+    backups: backups.entries.clone(),
+    backup_dir: backups.created(),
+  })
+  .unwrap();
+  println!("{}", stdout);
 }
@@ -0,0 +1,85 @@
+use crate::error::{Error, ErrorStage};
+use std::os::unix::fs::symlink as os_symlink;
+use std::path::{Component, Path, PathBuf};
+
+/// Makes `dst` a symbolic link containing `src`, which may be relative to the `dst` directory
+pub fn symlink(src: &Path, dst: &Path) -> Result<(), Error> {
+  match os_symlink(src, dst) {
+    Ok(_) => Ok(()),
+    Err(e) => match e.kind() {
+      std::io::ErrorKind::AlreadyExists => Err(Error {
+        kind: e.kind().into(),
+        message: "destination file already exists".to_owned(),
+        stage: ErrorStage::Symlink,
+      }),
+      std::io::ErrorKind::NotFound => Err(Error {
+        kind: e.kind().into(),
+        message: "destination directory was not found".to_owned(),
+        stage: ErrorStage::Symlink,
+      }),
+      _ => Err(Error {
+        kind: e.kind().into(),
+        message: e.to_string(),
+        stage: ErrorStage::Symlink,
+      }),
+    },
+  }
+}
+
+/// Builds a path to the `path` as seen from within the `base` directory,
+/// both are expected to be absolute
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+  let path: Vec<Component> = path.components().collect();
+  let base: Vec<Component> = base.components().collect();
+
+  let common = path
+    .iter()
+    .zip(base.iter())
+    .take_while(|(a, b)| a == b)
+    .count();
+
+  let mut p = PathBuf::new();
+
+  for _ in common..base.len() {
+    p.push("..");
+  }
+
+  for one in &path[common..] {
+    p.push(one);
+  }
+
+  p
+}
+
+#[cfg(test)]
+mod test_relative_path {
+  use super::relative_path;
+  use std::path::{Path, PathBuf};
+
+  #[test]
+  fn should_step_out_of_the_base_and_into_the_path() {
+    let actual = relative_path(
+      Path::new("/home/gman/dotfiles/files/linux/.zshrc"),
+      Path::new("/home/gman"),
+    );
+
+    assert_eq!(actual, PathBuf::from("dotfiles/files/linux/.zshrc"));
+  }
+
+  #[test]
+  fn should_climb_up_when_base_is_deeper() {
+    let actual = relative_path(
+      Path::new("/home/gman/dotfiles/files/kitty.conf"),
+      Path::new("/home/gman/.config/kitty"),
+    );
+
+    assert_eq!(actual, PathBuf::from("../../dotfiles/files/kitty.conf"));
+  }
+
+  #[test]
+  fn should_go_through_the_root_when_nothing_in_common() {
+    let actual = relative_path(Path::new("/opt/files/hosts"), Path::new("/etc"));
+
+    assert_eq!(actual, PathBuf::from("../opt/files/hosts"));
+  }
+}
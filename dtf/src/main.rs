@@ -1,11 +1,18 @@
-use cli::{self, App, Cli};
-use dtflib::{client_os, Context, DotFile};
+use cli::plan::Operation;
+use cli::{self, App, Backups, BackupsCmd, Cli, LinkArgs, MachineArgs, ModeArgs, Request};
+use dtflib::DotFile;
 use parser::Parser;
 use std::collections::HashMap;
 use std::io::Result;
+use std::path::PathBuf;
 
 mod validate_config;
-use validate_config::validate_config;
+
+mod setup;
+use setup::Setup;
+
+mod parse_config;
+use parse_config::{kept, parse_config, print_skipped};
 
 fn main() -> Result<()> {
   let args: Vec<String> = std::env::args()
@@ -20,65 +27,285 @@ fn main() -> Result<()> {
     Some(_) => true,
   };
 
+  if !child && !matches!(app, Cli::Rollback) {
+    cli::recover(home_dir, app.changes_files())?;
+  }
+
   match app {
-    Cli::Link { config, force, os } => {
-      let (config_path, base_dir) = &validate_config(&config);
-      let client_os = client_os::digest(os);
-
-      let cx = Context {
-        config_path,
-        base_dir,
-        client_os: &client_os,
-        home_dir,
-        child,
+    Cli::Link {
+      config,
+      force,
+      prune,
+      dry_run,
+      atomic,
+      create_dirs,
+      dir_mode,
+      link: LinkArgs {
+        mode: ModeArgs { mode },
+        relative,
+      },
+      machine,
+    } => {
+      let setup = Setup::new(&config, machine);
+      let cx = setup.cx(home_dir, child);
+
+      let opts = cli::LinkOpts {
+        force,
+        mode,
+        relative,
+        dry_run,
+        atomic,
+        create_dirs,
+        dir_mode,
       };
 
       let mut parser = Parser::with(&cx);
 
       if cx.is_main() {
-        let dotfiles = parser.parse(&config_path)?;
+        let (dotfiles, skipped) = parse_config(&mut parser, cx.config_path)?;
+        let backups = &mut Backups::new(home_dir);
 
-        cli::link(&cx, &dotfiles, force)?;
+        let linked = cli::link_all(&cx, &dotfiles, &opts, backups)?;
+
+        // what is gone from the config goes away even when something else failed to link
+        if prune {
+          cli::prune(&cx, &dotfiles, &kept(&skipped), dry_run, backups)?;
+        }
+
+        if !linked {
+          std::process::exit(1);
+        }
       } else {
-        let mut dotfiles_json = String::with_capacity(256);
-        std::io::stdin().read_line(&mut dotfiles_json)?;
+        let mut request_json = String::with_capacity(256);
+        std::io::stdin().read_line(&mut request_json)?;
 
-        let dotfiles: HashMap<u32, DotFile> = serde_json::from_str(&dotfiles_json)?;
+        let request: Request<Vec<Operation>> = serde_json::from_str(&request_json)?;
 
-        cli::link(&cx, &dotfiles, force)?;
+        cli::apply(request)?;
       }
     }
-    Cli::List { config, os } => {
-      let (config_path, base_dir) = &validate_config(&config);
-      let client_os = client_os::digest(os);
-
-      let cx = Context {
-        config_path,
-        base_dir,
-        client_os: &client_os,
-        home_dir,
-        child,
+    Cli::Adopt {
+      config,
+      path,
+      target,
+      section,
+      link: LinkArgs {
+        mode: ModeArgs { mode },
+        relative,
+      },
+      machine,
+    } => {
+      let setup = Setup::new(&config, machine);
+      let cx = setup.cx(home_dir, child);
+
+      let opts = cli::LinkOpts {
+        force: false,
+        mode,
+        relative,
+        dry_run: false,
+        atomic: false,
+        create_dirs: false,
+        dir_mode: None,
+      };
+
+      // linking the adopted file back might need the sudo child, the rest is main only
+      if !cx.is_main() {
+        let mut request_json = String::with_capacity(256);
+        std::io::stdin().read_line(&mut request_json)?;
+
+        let request: Request<Vec<Operation>> = serde_json::from_str(&request_json)?;
+
+        return cli::apply(request);
+      }
+
+      // the file itself might be a symlink, so only its directory gets resolved
+      let path = std::env::current_dir()?.join(&path);
+      let dir = path.parent().unwrap().canonicalize()?;
+      let path = dir.join(path.file_name().unwrap());
+
+      let mut parser = Parser::with(&cx);
+      parser.read_config(cx.config_path)?;
+
+      let adoption = parser.adopt(&path, target.as_deref(), section)?;
+
+      let dotfile = DotFile {
+        id: 1,
+        name: adoption.name.as_str().into(),
+        dst_name: None,
+        src: adoption.src.clone(),
+        dst: adoption.dst.clone(),
+        link: None,
+        relative: None,
+        mkdir: None,
+        dir_mode: None,
+        pattern: None,
+        candidates: vec![],
       };
 
+      cli::adopt(&cx, &dotfile)?;
+
+      if let Err(e) = parser.write_config(cx.config_path) {
+        cli::disown(&cx, &dotfile)?;
+        return Err(e);
+      }
+
+      let mut dotfiles = HashMap::with_capacity(1);
+      dotfiles.insert(dotfile.id, dotfile);
+
+      cli::link(&cx, &dotfiles, &opts, &mut Backups::new(home_dir))?;
+    }
+    Cli::Prune {
+      config,
+      dry_run,
+      machine,
+    } => {
+      let setup = Setup::new(&config, machine);
+      let cx = setup.cx(home_dir, child);
+
       let mut parser = Parser::with(&cx);
-      let dotfiles = parser.parse(&config_path)?;
 
-      cli::list(&cx, &dotfiles)?;
+      if cx.is_main() {
+        let (dotfiles, skipped) = parse_config(&mut parser, cx.config_path)?;
+        let backups = &mut Backups::new(home_dir);
+
+        cli::prune(&cx, &dotfiles, &kept(&skipped), dry_run, backups)?;
+      } else {
+        let mut request_json = String::with_capacity(256);
+        std::io::stdin().read_line(&mut request_json)?;
+
+        let request: Request<DotFile> = serde_json::from_str(&request_json)?;
+        let backups = &mut Backups::at(request.backup_dir, request.backup_owner);
+
+        cli::prune(&cx, &request.dotfiles, &[], dry_run, backups)?;
+      }
     }
-    Cli::Show { config } => {
-      let (config_path, base_dir) = &validate_config(&config);
-      let client_os = client_os::digest(None);
-
-      let cx = Context {
-        config_path,
-        base_dir,
-        client_os: &client_os,
-        home_dir,
-        child,
+    Cli::Unlink { config, machine } => {
+      let setup = Setup::new(&config, machine);
+      let cx = setup.cx(home_dir, child);
+
+      let mut parser = Parser::with(&cx);
+
+      if cx.is_main() {
+        let (dotfiles, _) = parse_config(&mut parser, cx.config_path)?;
+        let backups = &mut Backups::new(home_dir);
+
+        cli::unlink(&cx, &dotfiles, backups)?;
+      } else {
+        let mut request_json = String::with_capacity(256);
+        std::io::stdin().read_line(&mut request_json)?;
+
+        let request: Request<DotFile> = serde_json::from_str(&request_json)?;
+        let backups = &mut Backups::at(request.backup_dir, request.backup_owner);
+
+        cli::unlink(&cx, &request.dotfiles, backups)?;
+      }
+    }
+    Cli::List {
+      config,
+      link: LinkArgs {
+        mode: ModeArgs { mode },
+        relative,
+      },
+      machine,
+      explain,
+    } => {
+      let setup = Setup::new(&config, machine);
+      let cx = setup.cx(home_dir, child);
+
+      let mut parser = Parser::with(&cx);
+      let (dotfiles, skipped) = parse_config(&mut parser, cx.config_path)?;
+
+      cli::list(&cx, &dotfiles, mode, relative, explain)?;
+
+      if explain {
+        print_skipped(&skipped);
+      }
+    }
+    Cli::Status {
+      config,
+      porcelain,
+      mode: ModeArgs { mode },
+      machine,
+    } => {
+      let setup = Setup::new(&config, machine);
+      let cx = setup.cx(home_dir, child);
+
+      let mut parser = Parser::with(&cx);
+      let (dotfiles, _) = parse_config(&mut parser, cx.config_path)?;
+
+      cli::status(&cx, &dotfiles, mode, porcelain)?;
+    }
+    Cli::Repair {
+      config,
+      prefer,
+      link: LinkArgs {
+        mode: ModeArgs { mode },
+        relative,
+      },
+      machine,
+    } => {
+      let setup = Setup::new(&config, machine);
+      let cx = setup.cx(home_dir, child);
+
+      let opts = cli::LinkOpts {
+        force: true,
+        mode,
+        relative,
+        dry_run: false,
+        atomic: false,
+        create_dirs: false,
+        dir_mode: None,
       };
 
       let mut parser = Parser::with(&cx);
-      parser.read_config(config_path)?;
+
+      if cx.is_main() {
+        let (dotfiles, _) = parse_config(&mut parser, cx.config_path)?;
+        let backups = &mut Backups::new(home_dir);
+
+        cli::repair(&cx, &dotfiles, &opts, prefer, backups)?;
+      } else {
+        let mut request_json = String::with_capacity(256);
+        std::io::stdin().read_line(&mut request_json)?;
+
+        let request: Request<Vec<Operation>> = serde_json::from_str(&request_json)?;
+
+        cli::apply(request)?;
+      }
+    }
+    Cli::Pull {
+      config,
+      mode: ModeArgs { mode },
+      machine,
+    } => {
+      let setup = Setup::new(&config, machine);
+      let cx = setup.cx(home_dir, child);
+
+      let mut parser = Parser::with(&cx);
+      let (dotfiles, _) = parse_config(&mut parser, cx.config_path)?;
+
+      cli::pull(&cx, &dotfiles, mode)?;
+    }
+    Cli::Backups(BackupsCmd::List) => {
+      cli::backups_list(home_dir)?;
+    }
+    Cli::Restore { run_id, paths } => {
+      let paths: Vec<PathBuf> = paths
+        .iter()
+        .map(|path| std::env::current_dir().map(|cwd| cwd.join(path)))
+        .collect::<Result<_>>()?;
+
+      cli::restore(home_dir, &run_id, &paths, child)?;
+    }
+    Cli::Rollback => {
+      cli::rollback(home_dir, child)?;
+    }
+    Cli::Show { config } => {
+      let setup = Setup::new(&config, MachineArgs::default());
+      let cx = setup.cx(home_dir, child);
+
+      let mut parser = Parser::with(&cx);
+      parser.read_config(cx.config_path)?;
 
       let config_str = serde_json::to_string_pretty(parser.config().unwrap()).unwrap();
 
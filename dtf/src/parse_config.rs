@@ -0,0 +1,40 @@
+use colored::Colorize;
+use dtflib::DotFile;
+use parser::{Diagnostic, Level, Parser};
+use std::collections::HashMap;
+use std::io::Result;
+use std::path::PathBuf;
+
+/// Maps the config, telling about whatever looks off in it on the way. The blocks left
+/// out are handed back, for `ls --explain` to tell why
+pub fn parse_config<'a>(
+  parser: &'a mut Parser,
+  config_path: &PathBuf,
+) -> Result<(HashMap<u32, DotFile<'a>>, Vec<Diagnostic>)> {
+  let (dotfiles, diagnostics) = parser.parse(config_path)?;
+  let (warnings, skipped): (Vec<Diagnostic>, Vec<Diagnostic>) = diagnostics
+    .into_iter()
+    .partition(|one| one.level == Level::Warning);
+
+  for one in warnings {
+    eprintln!("|> {}: {}", one.name.bold(), one.message.yellow());
+  }
+
+  Ok((dotfiles, skipped))
+}
+
+/// Lists the blocks left out along with why
+pub fn print_skipped(skipped: &[Diagnostic]) {
+  for one in skipped {
+    println!("{} {}", one.name.bold(), "(skipped)".dimmed());
+    println!("\t{}", one.message.dimmed());
+  }
+}
+
+/// Destinations of the blocks left out on their conditions, which are not to be pruned
+pub fn kept(skipped: &[Diagnostic]) -> Vec<PathBuf> {
+  skipped
+    .iter()
+    .flat_map(|one| one.dsts.iter().cloned())
+    .collect()
+}
@@ -0,0 +1,36 @@
+use crate::validate_config::validate_config;
+use cli::MachineArgs;
+use dtflib::{client_os, machine, Context, Machine};
+use std::path::PathBuf;
+
+/// Whatever a command that maps the config runs against, for its `Context` to borrow
+pub struct Setup {
+  config_path: PathBuf,
+  base_dir: PathBuf,
+  client_os: client_os::Os,
+  machine: Machine,
+}
+
+impl Setup {
+  pub fn new(config: &PathBuf, args: MachineArgs) -> Self {
+    let (config_path, base_dir) = validate_config(config);
+
+    Self {
+      config_path,
+      base_dir,
+      client_os: client_os::digest(args.os, args.os_version),
+      machine: machine::digest(args.host, args.user, args.arch),
+    }
+  }
+
+  pub fn cx<'a>(&'a self, home_dir: &'a PathBuf, child: bool) -> Context<'a> {
+    Context {
+      config_path: &self.config_path,
+      base_dir: &self.base_dir,
+      client_os: &self.client_os,
+      machine: &self.machine,
+      home_dir,
+      child,
+    }
+  }
+}
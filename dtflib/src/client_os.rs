@@ -1,47 +1,51 @@
-#[derive(Debug, PartialEq)]
-pub enum Type {
-  /// Alpine Linux (<https://en.wikipedia.org/wiki/Alpine_Linux>).
-  Alpine,
-  /// Amazon Linux AMI (<https://en.wikipedia.org/wiki/Amazon_Machine_Image#Amazon_Linux_AMI>).
-  Amazon,
-  /// Arch Linux (<https://en.wikipedia.org/wiki/Arch_Linux>).
-  Arch,
-  /// CentOS (<https://en.wikipedia.org/wiki/CentOS>).
-  Centos,
-  /// Debian (<https://en.wikipedia.org/wiki/Debian>).
-  Debian,
-  /// Fedora (<https://en.wikipedia.org/wiki/Fedora_(operating_system)>).
-  Fedora,
-  /// Linux based operating system (<https://en.wikipedia.org/wiki/Linux>).
-  Linux,
-  /// Mac OS X/OS X/macOS (<https://en.wikipedia.org/wiki/MacOS>).
-  Macos,
-  /// Manjaro (<https://en.wikipedia.org/wiki/Manjaro>).
-  Manjaro,
-  /// openSUSE (<https://en.wikipedia.org/wiki/OpenSUSE>).
-  OpenSUSE,
-  /// Oracle Linux (<https://en.wikipedia.org/wiki/Oracle_Linux>).
-  OracleLinux,
-  /// Pop!_OS (<https://en.wikipedia.org/wiki/Pop!_OS>)
-  Pop,
-  /// Red Hat Linux (<https://en.wikipedia.org/wiki/Red_Hat_Linux>).
-  Redhat,
-  /// Red Hat Enterprise Linux (<https://en.wikipedia.org/wiki/Red_Hat_Enterprise_Linux>).
-  RedHatEnterprise,
-  /// Redox (<https://en.wikipedia.org/wiki/Redox_(operating_system)>).
-  Redox,
-  /// Solus (<https://en.wikipedia.org/wiki/Solus_(operating_system)>).
-  Solus,
-  /// SUSE Linux Enterprise Server (<https://en.wikipedia.org/wiki/SUSE_Linux_Enterprise>).
-  SUSE,
-  /// Ubuntu (<https://en.wikipedia.org/wiki/Ubuntu_(operating_system)>).
-  Ubuntu,
-  /// Windows (<https://en.wikipedia.org/wiki/Microsoft_Windows>).
-  Windows,
-  /// Unknown operating system.
-  Unknown,
+use crate::{OsRelease, Version};
+use std::path::Path;
+
+macro_rules! define_type {
+  ($($(#[$doc:meta])* $variant:ident $dir:literal [$($alias:literal),*] ($($parent:ident)?),)*) => {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Type {
+      $($(#[$doc])* $variant,)*
+      /// Unknown operating system.
+      Unknown,
+    }
+
+    impl std::convert::From<&str> for Type {
+      /// Reads the name of a system, any of its aliases or its os-release `ID`
+      fn from(val: &str) -> Self {
+        match val.to_lowercase().as_str() {
+          $($dir $(| $alias)* => Type::$variant,)*
+          _ => Type::Unknown,
+        }
+      }
+    }
+
+    impl Type {
+      pub fn all<'a>() -> &'a [Self] {
+        &[$(Type::$variant,)* Type::Unknown]
+      }
+
+      /// Directory name of the system, which is the name of its target as well
+      pub fn dir(&self) -> &'static str {
+        match self {
+          $(Type::$variant => $dir,)*
+          Type::Unknown => "unknown",
+        }
+      }
+
+      /// The family the system belongs to, if any
+      pub fn parent(&self) -> Option<Type> {
+        match self {
+          $(Type::$variant => None$(.or(Some(Type::$parent)))?,)*
+          Type::Unknown => None,
+        }
+      }
+    }
+  };
 }
 
+crate::with_systems!(define_type);
+
 impl std::convert::From<os_info::Type> for Type {
   fn from(os_info_type: os_info::Type) -> Self {
     match &os_info_type {
@@ -74,61 +78,198 @@ impl std::convert::From<os_info::Type> for Type {
   }
 }
 
-impl std::convert::From<&str> for Type {
-  fn from(val: &str) -> Self {
-    match val {
-      "alpine" => Type::Alpine,
-      "amazon" => Type::Amazon,
-      "arch" => Type::Arch,
-      "centos" => Type::Centos,
-      "debian" => Type::Debian,
-      "fedora" => Type::Fedora,
-      "linux" => Type::Linux,
-      "macos" => Type::Macos,
-      "manjaro" => Type::Manjaro,
-      "opensuse" => Type::OpenSUSE,
-      "oraclelinux" => Type::OracleLinux,
-      "pop" => Type::Pop,
-      "redhat" => Type::Redhat,
-      "redhatenterprise" => Type::RedHatEnterprise,
-      "redox" => Type::Redox,
-      "solus" => Type::Solus,
-      "suse" => Type::SUSE,
-      "ubuntu" => Type::Ubuntu,
-      "windows" => Type::Windows,
-      _ => Type::Unknown,
+impl Type {
+  /// The system itself followed by its families, up to the platform, e.g. `Mint`,
+  /// `Ubuntu`, `Debian`, `DebianFamily`, `Linux`
+  pub fn ancestors(&self) -> Vec<Type> {
+    let mut ret = vec![*self];
+
+    while let Some(parent) = ret.last().unwrap().parent() {
+      ret.push(parent);
+    }
+
+    ret
+  }
+
+  /// How far the system is from its platform, which is at `0`
+  pub fn depth(&self) -> u8 {
+    self.ancestors().len() as u8 - 1
+  }
+}
+
+/// What is known about the OS dtf is running on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Os {
+  pub kind: Type,
+  /// Systems this one is derived from, as its os-release tells, the closest first
+  pub like: Vec<Type>,
+  /// Nothing is known beyond the `kind` without an os-release, e.g. given with `--os`
+  pub release: Option<OsRelease>,
+  pub version: Option<Version>,
+}
+
+impl std::convert::From<Type> for Os {
+  fn from(kind: Type) -> Self {
+    Self {
+      kind,
+      like: vec![],
+      release: None,
+      version: None,
     }
   }
 }
 
-impl Type {
-  pub fn all<'a>() -> &'a [Self] {
-    &[
-      Type::Alpine,
-      Type::Amazon,
-      Type::Arch,
-      Type::Centos,
-      Type::Debian,
-      Type::Fedora,
-      Type::Linux,
-      Type::Macos,
-      Type::Manjaro,
-      Type::OpenSUSE,
-      Type::OracleLinux,
-      Type::Pop,
-      Type::Redhat,
-      Type::RedHatEnterprise,
-      Type::Redox,
-      Type::Solus,
-      Type::SUSE,
-      Type::Ubuntu,
-      Type::Windows,
-      Type::Unknown,
-    ]
+impl std::convert::From<OsRelease> for Os {
+  fn from(release: OsRelease) -> Self {
+    // whatever has an os-release is a Linux, even when dtf doesn't know its name
+    let kind = match Type::from(release.id.as_str()) {
+      Type::Unknown => Type::Linux,
+      kind => kind,
+    };
+
+    let like = release
+      .id_like
+      .iter()
+      .map(|id| Type::from(id.as_str()))
+      .filter(|one| *one != Type::Unknown && *one != kind)
+      .collect();
+
+    Self {
+      kind,
+      like,
+      version: release.version_id.as_ref().and_then(|val| val.parse().ok()),
+      release: Some(release),
+    }
+  }
+}
+
+/// The system the platform dtf was built for stands for, if that tells it already
+fn from_platform(os: &str) -> Option<Type> {
+  match os {
+    "freebsd" => Some(Type::FreeBSD),
+    "openbsd" => Some(Type::OpenBSD),
+    "netbsd" => Some(Type::NetBSD),
+    "macos" => Some(Type::Macos),
+    "windows" => Some(Type::Windows),
+    _ => None,
+  }
+}
+
+/// Tells what the system found at `root` is, its os-release knows best. Without one,
+/// the platform dtf was built for goes before os_info, which knows nothing of the BSDs
+pub fn detect(root: &Path) -> Os {
+  match OsRelease::read(root) {
+    Ok(release) => Os::from(release),
+    Err(_) => {
+      let info = os_info::get();
+      let kind = from_platform(std::env::consts::OS).unwrap_or_else(|| info.os_type().into());
+
+      Os {
+        version: info.version().version().to_string().parse().ok(),
+        ..Os::from(kind)
+      }
+    }
   }
 }
 
+/// The OS given by the user, or the one dtf is running on. A `version` given by the user
+/// wins over the detected one
 #[inline]
-pub fn digest(input: Option<Type>) -> Type {
-  input.unwrap_or_else(|| Type::from(os_info::get().os_type()))
+pub fn digest(input: Option<Type>, version: Option<Version>) -> Os {
+  let os = match input {
+    Some(kind) => Os::from(kind),
+    None => detect(Path::new("/")),
+  };
+
+  match version {
+    Some(version) => Os {
+      version: Some(version),
+      ..os
+    },
+    None => os,
+  }
+}
+
+#[cfg(test)]
+mod test_detect {
+  use super::{detect, from_platform, Type};
+  use std::path::PathBuf;
+
+  fn root(name: &str) -> PathBuf {
+    std::env::current_dir()
+      .unwrap()
+      .join("examples")
+      .join("os-release")
+      .join(name)
+  }
+
+  #[test]
+  fn should_tell_the_distro_and_the_ones_it_is_like() {
+    let os = detect(&root("mint"));
+
+    assert_eq!(os.kind, Type::Mint);
+    assert_eq!(os.like, vec![Type::Ubuntu, Type::Debian]);
+    assert_eq!(os.version, Some("21.2".parse().unwrap()));
+    assert_eq!(os.release.unwrap().id, "linuxmint");
+  }
+
+  #[test]
+  fn should_tell_the_system_from_the_platform_without_an_os_release() {
+    assert_eq!(from_platform("openbsd"), Some(Type::OpenBSD));
+    assert_eq!(from_platform("netbsd"), Some(Type::NetBSD));
+    assert_eq!(from_platform("freebsd"), Some(Type::FreeBSD));
+    assert_eq!(from_platform("macos"), Some(Type::Macos));
+    assert_eq!(from_platform("windows"), Some(Type::Windows));
+    assert_eq!(from_platform("linux"), None);
+  }
+
+  #[test]
+  fn should_read_ids_that_differ_from_the_names() {
+    let os = detect(&root("rocky"));
+
+    assert_eq!(os.kind, Type::Rocky);
+    assert_eq!(
+      os.like,
+      vec![Type::RedHatEnterprise, Type::Centos, Type::Fedora]
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_ancestors {
+  use super::Type;
+
+  #[test]
+  fn should_walk_up_to_the_platform() {
+    assert_eq!(
+      Type::Mint.ancestors(),
+      vec![
+        Type::Mint,
+        Type::Ubuntu,
+        Type::Debian,
+        Type::DebianFamily,
+        Type::Linux
+      ]
+    );
+    assert_eq!(Type::FreeBSD.ancestors(), vec![Type::FreeBSD, Type::Bsd]);
+    assert_eq!(Type::Macos.ancestors(), vec![Type::Macos]);
+  }
+
+  #[test]
+  fn every_system_but_unknown_should_have_a_platform() {
+    let platforms = [Type::Linux, Type::Bsd, Type::Macos, Type::Windows];
+
+    for os in Type::all().iter().filter(|os| **os != Type::Unknown) {
+      let top = *os.ancestors().last().unwrap();
+      assert!(platforms.contains(&top), "{:?} ends up at {:?}", os, top);
+    }
+  }
+
+  #[test]
+  fn should_read_names_aliases_and_ids() {
+    assert_eq!(Type::from("debian-family"), Type::DebianFamily);
+    assert_eq!(Type::from("linuxmint"), Type::Mint);
+    assert_eq!(Type::from("Darwin"), Type::Macos);
+    assert_eq!(Type::from("plan9"), Type::Unknown);
+  }
 }
@@ -1,11 +1,12 @@
-use crate::client_os;
+use crate::{client_os, Machine};
 use std::path::PathBuf;
 
 #[derive(Debug)]
 pub struct Context<'a> {
   pub config_path: &'a PathBuf,
   pub base_dir: &'a PathBuf,
-  pub client_os: &'a client_os::Type,
+  pub client_os: &'a client_os::Os,
+  pub machine: &'a Machine,
   pub home_dir: &'a PathBuf,
 
   /// whether the current process is a child (spawned) or main
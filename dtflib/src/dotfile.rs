@@ -1,22 +1,106 @@
+use crate::LinkMode;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::path::PathBuf;
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DotFile<'a> {
   pub id: u32,
-  pub name: &'a str,
+  /// Path of the file relative to both `src` and `dst`, it only differs from the block
+  /// name for the files found within a recursive block
+  pub name: Cow<'a, str>,
+
+  /// Name of the file at the destination when it differs from the `name`
+  #[serde(default)]
+  pub dst_name: Option<Cow<'a, str>>,
+
   pub src: PathBuf,
   pub dst: PathBuf,
+
+  /// Per-file link mode, overrides the one given for the whole run
+  #[serde(default)]
+  pub link: Option<LinkMode>,
+
+  /// Per-file preference for a relative symlink over an absolute one
+  #[serde(default)]
+  pub relative: Option<bool>,
+
+  /// Whether missing destination directories should be created, overrides the run-wide choice
+  #[serde(default)]
+  pub mkdir: Option<bool>,
+
+  /// Permissions of the destination directories created for this file
+  #[serde(default)]
+  pub dir_mode: Option<u32>,
+
+  /// Block name the file was found by, for glob patterns and recursive blocks
+  #[serde(default)]
+  pub pattern: Option<Cow<'a, str>>,
+
+  /// Source directories the file has been looked for in, the most specific target first.
+  /// Empty when the targets leave only one place to look
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub candidates: Vec<PathBuf>,
 }
 
 impl<'a> DotFile<'a> {
   /// Compiles final `to` path
   pub fn dst_file_path(&self) -> PathBuf {
-    PathBuf::from(&self.dst).join(self.name)
+    PathBuf::from(&self.dst).join(self.dst_name.as_deref().unwrap_or(&self.name))
   }
 
   /// Compiles final `from` path
   pub fn src_file_path(&self) -> PathBuf {
-    PathBuf::from(&self.src).join(self.name)
+    PathBuf::from(&self.src).join(self.name.as_ref())
+  }
+
+  /// Picks the link mode for this file, falling back to the run-wide one
+  pub fn link_mode(&self, default: LinkMode) -> LinkMode {
+    self.link.unwrap_or(default)
+  }
+
+  /// Whether a symlink for this file should be relative, falling back to the run-wide choice
+  pub fn is_relative(&self, default: bool) -> bool {
+    self.relative.unwrap_or(default)
+  }
+
+  /// Whether missing destination directories should be created, falling back to the
+  /// run-wide choice
+  pub fn creates_dirs(&self, default: bool) -> bool {
+    self.mkdir.unwrap_or(default)
+  }
+
+  /// Permissions of the created destination directories, falling back to the run-wide ones
+  pub fn dir_mode(&self, default: Option<u32>) -> Option<u32> {
+    self.dir_mode.or(default)
+  }
+}
+
+/// Reads permissions given in octal, like `755` or `0o700`
+pub fn parse_mode(val: &str) -> Result<u32, String> {
+  let digits = val.trim_start_matches("0o");
+
+  match u32::from_str_radix(digits, 8) {
+    Ok(mode) if mode <= 0o7777 => Ok(mode),
+    _ => Err(format!("`{}` is not an octal mode", val)),
+  }
+}
+
+#[cfg(test)]
+mod test_parse_mode {
+  use super::parse_mode;
+
+  #[test]
+  fn should_read_octal_digits() {
+    assert_eq!(parse_mode("755"), Ok(0o755));
+    assert_eq!(parse_mode("0700"), Ok(0o700));
+    assert_eq!(parse_mode("0o750"), Ok(0o750));
+  }
+
+  #[test]
+  fn should_refuse_anything_else() {
+    assert!(parse_mode("rwx").is_err());
+    assert!(parse_mode("789").is_err());
+    assert!(parse_mode("17777").is_err());
   }
 }
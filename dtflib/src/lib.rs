@@ -1,8 +1,27 @@
+mod systems;
+
 pub mod client_os;
 
+pub mod os_release;
+pub use os_release::OsRelease;
+
+mod version;
+pub use version::Version;
+
+pub mod machine;
+pub use machine::Machine;
+
 pub mod dotfile;
 pub use dotfile::DotFile;
 
+mod link_mode;
+pub use link_mode::LinkMode;
+
+pub mod xdg;
+
+pub mod manifest;
+pub use manifest::Manifest;
+
 mod context;
 pub use context::Context;
 
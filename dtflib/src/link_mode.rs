@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The way a dotfile reaches its destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkMode {
+  #[default]
+  #[serde(alias = "hard", alias = "hard_link")]
+  HardLink,
+
+  #[serde(alias = "soft", alias = "sym")]
+  Symlink,
+
+  Copy,
+}
+
+impl LinkMode {
+  pub fn variants<'a>() -> &'a [&'a str] {
+    &["hardlink", "symlink", "copy"]
+  }
+}
+
+impl FromStr for LinkMode {
+  type Err = String;
+
+  fn from_str(val: &str) -> Result<Self, Self::Err> {
+    match val {
+      "hardlink" | "hard" => Ok(Self::HardLink),
+      "symlink" | "soft" => Ok(Self::Symlink),
+      "copy" => Ok(Self::Copy),
+      _ => Err(format!("unknown link mode `{}`", val)),
+    }
+  }
+}
+
+impl fmt::Display for LinkMode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      LinkMode::HardLink => write!(f, "hardlink"),
+      LinkMode::Symlink => write!(f, "symlink"),
+      LinkMode::Copy => write!(f, "copy"),
+    }
+  }
+}
@@ -0,0 +1,64 @@
+use std::ffi::CStr;
+
+/// Facts about the machine dtf is running on, beyond its OS
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Machine {
+  pub host: String,
+  pub user: String,
+  /// CPU architecture as Rust names it, e.g. `x86_64` or `aarch64`
+  pub arch: String,
+}
+
+impl Machine {
+  pub fn detect() -> Self {
+    Self {
+      host: hostname().unwrap_or_default(),
+      user: username().unwrap_or_default(),
+      arch: std::env::consts::ARCH.to_owned(),
+    }
+  }
+}
+
+/// The machine dtf is running on, with whatever the user has given in place of its facts
+pub fn digest(host: Option<String>, user: Option<String>, arch: Option<String>) -> Machine {
+  let detected = Machine::detect();
+
+  Machine {
+    host: host.unwrap_or(detected.host),
+    user: user.unwrap_or(detected.user),
+    arch: arch.unwrap_or(detected.arch),
+  }
+}
+
+/// Other names the architectures go by, e.g. `arm64` on macOS, `amd64` on Debian
+pub fn canonical_arch(arch: &str) -> &str {
+  match arch {
+    "amd64" | "x64" => "x86_64",
+    "arm64" => "aarch64",
+    "i386" | "i686" => "x86",
+    arch => arch,
+  }
+}
+
+fn hostname() -> Option<String> {
+  let mut buf = [0 as libc::c_char; 256];
+
+  if unsafe { libc::gethostname(buf.as_mut_ptr(), buf.len()) } != 0 {
+    return None;
+  }
+
+  let name = unsafe { CStr::from_ptr(buf.as_ptr()) };
+  name.to_str().ok().map(str::to_owned)
+}
+
+fn username() -> Option<String> {
+  // `$USER` is whatever the environment says, the effective user is who dtf runs as
+  let passwd = unsafe { libc::getpwuid(libc::geteuid()) };
+
+  if !passwd.is_null() {
+    let name = unsafe { CStr::from_ptr((*passwd).pw_name) };
+    return name.to_str().ok().map(str::to_owned);
+  }
+
+  std::env::var("USER").ok()
+}
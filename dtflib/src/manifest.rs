@@ -0,0 +1,253 @@
+use crate::{xdg, LinkMode};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A single destination installed by dtf
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+  pub src: PathBuf,
+  pub dst: PathBuf,
+  pub strategy: LinkMode,
+
+  /// Inode of the destination, for links
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub inode: Option<u64>,
+
+  /// Content hash of the destination, for copies
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub hash: Option<String>,
+
+  /// Unix time of the moment it was installed
+  pub timestamp: u64,
+}
+
+impl Record {
+  pub fn now() -> u64 {
+    SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_secs())
+      .unwrap_or(0)
+  }
+}
+
+/// Everything dtf has installed so far, grouped by the config that asked for it
+/// and keyed by the destination path within a config
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+  #[serde(skip)]
+  path: PathBuf,
+
+  configs: HashMap<PathBuf, HashMap<PathBuf, Record>>,
+
+  /// Destination directories created by dtf, grouped by the config
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  dirs: HashMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl Manifest {
+  /// Reads the manifest from the state directory, an empty one if there is none yet
+  pub fn load(home_dir: &Path) -> io::Result<Self> {
+    Self::open(xdg::state_dir(home_dir).join("state.json"))
+  }
+
+  fn open(path: PathBuf) -> io::Result<Self> {
+    let mut manifest: Self = match fs::File::open(&path) {
+      Ok(f) => serde_json::from_reader(f)?,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+      Err(e) => return Err(e),
+    };
+
+    manifest.path = path;
+    Ok(manifest)
+  }
+
+  pub fn save(&self) -> io::Result<()> {
+    if let Some(dir) = self.path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+
+    let json = serde_json::to_string_pretty(self)?;
+    fs::write(&self.path, json)
+  }
+
+  /// All the records of the config, in no particular order
+  pub fn records(&self, config: &Path) -> Vec<&Record> {
+    match self.configs.get(config) {
+      Some(records) => records.values().collect(),
+      None => vec![],
+    }
+  }
+
+  pub fn get(&self, config: &Path, dst: &Path) -> Option<&Record> {
+    self
+      .configs
+      .get(config)
+      .and_then(|records| records.get(dst))
+  }
+
+  pub fn record(&mut self, config: &Path, record: Record) {
+    self
+      .configs
+      .entry(config.to_path_buf())
+      .or_default()
+      .insert(record.dst.clone(), record);
+  }
+
+  pub fn forget(&mut self, config: &Path, dst: &Path) -> Option<Record> {
+    let records = self.configs.get_mut(config)?;
+    let record = records.remove(dst);
+
+    if records.is_empty() {
+      self.configs.remove(config);
+    }
+
+    record
+  }
+
+  /// Directories created for the config, the deepest first
+  pub fn dirs(&self, config: &Path) -> Vec<&PathBuf> {
+    let mut dirs: Vec<&PathBuf> = match self.dirs.get(config) {
+      Some(dirs) => dirs.iter().collect(),
+      None => vec![],
+    };
+
+    dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+    dirs
+  }
+
+  pub fn record_dir(&mut self, config: &Path, dir: PathBuf) {
+    self
+      .dirs
+      .entry(config.to_path_buf())
+      .or_default()
+      .insert(dir);
+  }
+
+  pub fn forget_dir(&mut self, config: &Path, dir: &Path) -> bool {
+    let dirs = match self.dirs.get_mut(config) {
+      Some(val) => val,
+      None => return false,
+    };
+    let ok = dirs.remove(dir);
+
+    if dirs.is_empty() {
+      self.dirs.remove(config);
+    }
+
+    ok
+  }
+}
+
+#[cfg(test)]
+mod test_manifest {
+  use super::{Manifest, Record};
+  use crate::LinkMode;
+  use std::fs;
+  use std::path::{Path, PathBuf};
+
+  fn record(dst: &str) -> Record {
+    Record {
+      src: PathBuf::from("/repo").join(&dst[1..]),
+      dst: PathBuf::from(dst),
+      strategy: LinkMode::HardLink,
+      inode: Some(42),
+      hash: None,
+      timestamp: 1792321202,
+    }
+  }
+
+  #[test]
+  fn should_read_back_what_was_saved() {
+    let dir = std::env::temp_dir().join(format!("dtf-manifest-{}", std::process::id()));
+    let path = dir.join("state.json");
+    let config = Path::new("/repo/dtf.yml");
+
+    let mut manifest = Manifest::open(path.clone()).unwrap();
+    assert!(manifest.records(config).is_empty());
+
+    manifest.record(config, record("/home/gman/.vimrc"));
+    manifest.record(
+      config,
+      Record {
+        strategy: LinkMode::Copy,
+        inode: None,
+        hash: Some("abc".to_owned()),
+        ..record("/home/gman/.gitconfig")
+      },
+    );
+    manifest.record_dir(config, PathBuf::from("/home/gman/.config"));
+    manifest.save().unwrap();
+
+    let actual = Manifest::open(path).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+      actual.get(config, Path::new("/home/gman/.vimrc")),
+      Some(&record("/home/gman/.vimrc"))
+    );
+    assert_eq!(
+      actual
+        .get(config, Path::new("/home/gman/.gitconfig"))
+        .and_then(|one| one.hash.as_deref()),
+      Some("abc")
+    );
+    assert_eq!(actual.records(config).len(), 2);
+    assert_eq!(
+      actual.dirs(config),
+      vec![&PathBuf::from("/home/gman/.config")]
+    );
+  }
+
+  #[test]
+  fn should_keep_configs_apart() {
+    let one = Path::new("/repo/one.yml");
+    let two = Path::new("/repo/two.yml");
+    let dst = Path::new("/home/gman/.vimrc");
+
+    let mut manifest = Manifest::default();
+    manifest.record(one, record("/home/gman/.vimrc"));
+    manifest.record(
+      two,
+      Record {
+        inode: Some(7),
+        ..record("/home/gman/.vimrc")
+      },
+    );
+
+    assert_eq!(manifest.get(one, dst).and_then(|one| one.inode), Some(42));
+    assert_eq!(manifest.get(two, dst).and_then(|one| one.inode), Some(7));
+
+    assert_eq!(manifest.forget(one, dst), Some(record("/home/gman/.vimrc")));
+    assert_eq!(manifest.forget(one, dst), None);
+    assert!(manifest.records(one).is_empty());
+    assert_eq!(manifest.records(two).len(), 1);
+    assert!(!manifest.configs.contains_key(one));
+  }
+
+  #[test]
+  fn should_list_dirs_deepest_first() {
+    let config = Path::new("/repo/dtf.yml");
+    let mut manifest = Manifest::default();
+
+    manifest.record_dir(config, PathBuf::from("/home/gman/.config"));
+    manifest.record_dir(config, PathBuf::from("/home/gman/.config/nvim/lua"));
+    manifest.record_dir(config, PathBuf::from("/home/gman/.config/nvim"));
+
+    assert_eq!(
+      manifest.dirs(config),
+      vec![
+        &PathBuf::from("/home/gman/.config/nvim/lua"),
+        &PathBuf::from("/home/gman/.config/nvim"),
+        &PathBuf::from("/home/gman/.config"),
+      ]
+    );
+
+    assert!(manifest.forget_dir(config, Path::new("/home/gman/.config")));
+    assert!(!manifest.forget_dir(config, Path::new("/home/gman/.config")));
+    assert_eq!(manifest.dirs(config).len(), 2);
+  }
+}
@@ -0,0 +1,142 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Facts about a Linux distribution, as its os-release file tells them
+/// (<https://www.freedesktop.org/software/systemd/man/os-release.html>)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OsRelease {
+  /// `ID`, e.g. `linuxmint`
+  pub id: String,
+  /// `ID_LIKE`, the distributions this one is derived from, the closest first
+  pub id_like: Vec<String>,
+  /// `VERSION_ID`, e.g. `22.04`
+  pub version_id: Option<String>,
+  /// `VERSION_CODENAME`, e.g. `jammy`
+  pub version_codename: Option<String>,
+}
+
+impl OsRelease {
+  /// Reads the os-release of the system found at `root`, which is `/` for the running one.
+  /// `/etc/os-release` wins over `/usr/lib/os-release`
+  pub fn read(root: &Path) -> io::Result<Self> {
+    let body = match fs::read_to_string(root.join("etc/os-release")) {
+      Err(e) if e.kind() == io::ErrorKind::NotFound => {
+        fs::read_to_string(root.join("usr/lib/os-release"))?
+      }
+      res => res?,
+    };
+
+    Ok(Self::parse(&body))
+  }
+
+  pub fn parse(body: &str) -> Self {
+    let mut release = Self::default();
+
+    for line in body.lines().map(str::trim) {
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      let (key, val) = match line.split_once('=') {
+        Some((key, val)) => (key.trim(), unquote(val.trim())),
+        None => continue,
+      };
+
+      match key {
+        "ID" => release.id = val.to_lowercase(),
+        "ID_LIKE" => {
+          release.id_like = val.split_whitespace().map(str::to_lowercase).collect();
+        }
+        "VERSION_ID" => release.version_id = Some(val),
+        "VERSION_CODENAME" if !val.is_empty() => release.version_codename = Some(val),
+        _ => {}
+      }
+    }
+
+    // the spec says so when there is no ID
+    if release.id.is_empty() {
+      release.id = "linux".to_owned();
+    }
+
+    release
+  }
+}
+
+/// Values are shell-like, they may be quoted and escaped
+fn unquote(val: &str) -> String {
+  let quoted = val.len() >= 2
+    && (val.starts_with('"') && val.ends_with('"') || val.starts_with('\'') && val.ends_with('\''));
+
+  if !quoted {
+    return val.to_owned();
+  }
+
+  let mut ret = String::with_capacity(val.len());
+  let mut chars = val[1..val.len() - 1].chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => ret.extend(chars.next()),
+      c => ret.push(c),
+    }
+  }
+
+  ret
+}
+
+#[cfg(test)]
+mod test_os_release {
+  use super::OsRelease;
+  use std::path::PathBuf;
+
+  fn root(name: &str) -> PathBuf {
+    std::env::current_dir()
+      .unwrap()
+      .join("examples")
+      .join("os-release")
+      .join(name)
+  }
+
+  #[test]
+  fn should_read_the_facts_out_of_etc() {
+    let release = OsRelease::read(&root("mint")).unwrap();
+
+    assert_eq!(
+      release,
+      OsRelease {
+        id: "linuxmint".to_owned(),
+        id_like: vec!["ubuntu".to_owned(), "debian".to_owned()],
+        version_id: Some("21.2".to_owned()),
+        version_codename: Some("victoria".to_owned()),
+      }
+    );
+  }
+
+  #[test]
+  fn should_fall_back_to_usr_lib() {
+    let release = OsRelease::read(&root("rocky")).unwrap();
+
+    assert_eq!(release.id, "rocky");
+    assert_eq!(release.id_like, vec!["rhel", "centos", "fedora"]);
+    assert_eq!(release.version_id.as_deref(), Some("9.3"));
+    assert_eq!(release.version_codename, None);
+  }
+
+  #[test]
+  fn should_unquote_values_and_skip_comments() {
+    let release = OsRelease::parse(
+      "# a comment\nID='arch'\nNAME=\"Arch \\\"Linux\\\"\"\n\nBUILD_ID=rolling\nVERSION_CODENAME=\n",
+    );
+
+    assert_eq!(release.id, "arch");
+    assert!(release.id_like.is_empty());
+    assert_eq!(release.version_id, None);
+    assert_eq!(release.version_codename, None);
+  }
+
+  #[test]
+  fn should_default_the_id_to_linux() {
+    assert_eq!(OsRelease::parse("NAME=Linux\n").id, "linux");
+  }
+}
@@ -0,0 +1,89 @@
+/// Every system dtf knows of, in one place. Both `client_os::Type` and the targets of
+/// the config are made out of this table by handing it to the macro named `$then`.
+///
+/// Each entry reads `Variant "dir" [other names] (parent)`: the directory name is also
+/// the canonical name of the target, the other names are aliases along with the `ID`s
+/// of os-release, and the parent is the family the system belongs to. A system is
+/// compatible with all of its ancestors, e.g. Linux Mint with `ubuntu`, `debian`,
+/// `debian-family` and `linux`.
+#[macro_export]
+macro_rules! with_systems {
+  ($then:ident) => {
+    $then! {
+      /// Linux based operating system (<https://en.wikipedia.org/wiki/Linux>).
+      Linux "linux" [] (),
+      /// Any of the BSDs (<https://en.wikipedia.org/wiki/Berkeley_Software_Distribution>).
+      Bsd "bsd" ["bsd-family"] (),
+      /// Mac OS X/OS X/macOS (<https://en.wikipedia.org/wiki/MacOS>).
+      Macos "macos" ["darwin", "mac os", "osx"] (),
+      /// Windows (<https://en.wikipedia.org/wiki/Microsoft_Windows>).
+      Windows "windows" ["win"] (),
+
+      /// Debian and whatever is derived from it.
+      DebianFamily "debian-family" [] (Linux),
+      /// Red Hat Enterprise Linux, Fedora and their rebuilds.
+      RhelFamily "rhel-family" ["redhat-family"] (Linux),
+
+      /// Alpine Linux (<https://en.wikipedia.org/wiki/Alpine_Linux>).
+      Alpine "alpine" [] (Linux),
+      /// Amazon Linux AMI (<https://en.wikipedia.org/wiki/Amazon_Machine_Image#Amazon_Linux_AMI>).
+      Amazon "amazon" ["amzn"] (RhelFamily),
+      /// Arch Linux (<https://en.wikipedia.org/wiki/Arch_Linux>).
+      Arch "arch" ["archlinux"] (Linux),
+      /// CentOS (<https://en.wikipedia.org/wiki/CentOS>).
+      Centos "centos" [] (RedHatEnterprise),
+      /// Debian (<https://en.wikipedia.org/wiki/Debian>).
+      Debian "debian" [] (DebianFamily),
+      /// elementary OS (<https://en.wikipedia.org/wiki/Elementary_OS>).
+      Elementary "elementary" ["elementaryos"] (Ubuntu),
+      /// EndeavourOS (<https://en.wikipedia.org/wiki/EndeavourOS>).
+      EndeavourOS "endeavouros" ["endeavour"] (Arch),
+      /// Fedora (<https://en.wikipedia.org/wiki/Fedora_(operating_system)>).
+      Fedora "fedora" [] (RhelFamily),
+      /// Gentoo (<https://en.wikipedia.org/wiki/Gentoo_Linux>).
+      Gentoo "gentoo" [] (Linux),
+      /// Kali Linux (<https://en.wikipedia.org/wiki/Kali_Linux>).
+      Kali "kali" [] (Debian),
+      /// Manjaro (<https://en.wikipedia.org/wiki/Manjaro>).
+      Manjaro "manjaro" ["manjaro-arm"] (Arch),
+      /// Linux Mint (<https://en.wikipedia.org/wiki/Linux_Mint>).
+      Mint "mint" ["linuxmint"] (Ubuntu),
+      /// NixOS (<https://en.wikipedia.org/wiki/NixOS>).
+      NixOS "nixos" ["nix"] (Linux),
+      /// openSUSE Leap (<https://en.wikipedia.org/wiki/OpenSUSE>).
+      OpenSUSE "opensuse" ["opensuse-leap"] (Linux),
+      /// openSUSE Tumbleweed (<https://en.wikipedia.org/wiki/OpenSUSE#Tumbleweed>).
+      Tumbleweed "tumbleweed" ["opensuse-tumbleweed"] (OpenSUSE),
+      /// Oracle Linux (<https://en.wikipedia.org/wiki/Oracle_Linux>).
+      OracleLinux "oraclelinux" ["ol"] (RedHatEnterprise),
+      /// Pop!_OS (<https://en.wikipedia.org/wiki/Pop!_OS>)
+      Pop "pop" [] (Ubuntu),
+      /// Red Hat Linux (<https://en.wikipedia.org/wiki/Red_Hat_Linux>).
+      Redhat "redhat" [] (RhelFamily),
+      /// Red Hat Enterprise Linux (<https://en.wikipedia.org/wiki/Red_Hat_Enterprise_Linux>).
+      RedHatEnterprise "redhatenterprise" ["rhel", "redhat enterprise"] (RhelFamily),
+      /// Redox (<https://en.wikipedia.org/wiki/Redox_(operating_system)>).
+      Redox "redox" [] (Linux),
+      /// Rocky Linux (<https://en.wikipedia.org/wiki/Rocky_Linux>).
+      Rocky "rocky" [] (RedHatEnterprise),
+      /// AlmaLinux (<https://en.wikipedia.org/wiki/AlmaLinux>).
+      Alma "alma" ["almalinux"] (RedHatEnterprise),
+      /// Solus (<https://en.wikipedia.org/wiki/Solus_(operating_system)>).
+      Solus "solus" [] (Linux),
+      /// SUSE Linux Enterprise Server (<https://en.wikipedia.org/wiki/SUSE_Linux_Enterprise>).
+      #[allow(clippy::upper_case_acronyms)]
+      SUSE "suse" ["sles", "sled"] (Linux),
+      /// Ubuntu (<https://en.wikipedia.org/wiki/Ubuntu_(operating_system)>).
+      Ubuntu "ubuntu" [] (Debian),
+      /// Void Linux (<https://en.wikipedia.org/wiki/Void_Linux>).
+      Void "void" [] (Linux),
+
+      /// FreeBSD (<https://en.wikipedia.org/wiki/FreeBSD>).
+      FreeBSD "freebsd" [] (Bsd),
+      /// NetBSD (<https://en.wikipedia.org/wiki/NetBSD>).
+      NetBSD "netbsd" [] (Bsd),
+      /// OpenBSD (<https://en.wikipedia.org/wiki/OpenBSD>).
+      OpenBSD "openbsd" [] (Bsd),
+    }
+  };
+}
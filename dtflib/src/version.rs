@@ -0,0 +1,88 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Version of an OS, like `22.04` or `14.1.2`. Versions are compared part by part, the
+/// missing parts count as zeros, so `14` and `14.0` are the same version
+#[derive(Debug, Clone)]
+pub struct Version {
+  raw: String,
+  parts: Vec<u64>,
+}
+
+impl FromStr for Version {
+  type Err = String;
+
+  fn from_str(val: &str) -> Result<Self, Self::Err> {
+    let parts: Result<Vec<u64>, _> = val.trim().split('.').map(str::parse).collect();
+
+    match parts {
+      Ok(parts) => Ok(Self {
+        raw: val.trim().to_owned(),
+        parts,
+      }),
+      _ => Err(format!("`{}` is not a version", val)),
+    }
+  }
+}
+
+impl fmt::Display for Version {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.raw)
+  }
+}
+
+impl Ord for Version {
+  fn cmp(&self, other: &Self) -> Ordering {
+    let len = self.parts.len().max(other.parts.len());
+    let part = |parts: &[u64], i: usize| parts.get(i).copied().unwrap_or(0);
+
+    (0..len)
+      .map(|i| part(&self.parts, i).cmp(&part(&other.parts, i)))
+      .find(|ord| *ord != Ordering::Equal)
+      .unwrap_or(Ordering::Equal)
+  }
+}
+
+impl PartialOrd for Version {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl PartialEq for Version {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for Version {}
+
+#[cfg(test)]
+mod test_version {
+  use super::Version;
+
+  fn v(val: &str) -> Version {
+    val.parse().unwrap()
+  }
+
+  #[test]
+  fn should_compare_part_by_part() {
+    assert!(v("22.04") > v("20.10"));
+    assert!(v("14.1.2") > v("14.1"));
+    assert!(v("9") < v("10"));
+    assert_eq!(v("14"), v("14.0.0"));
+  }
+
+  #[test]
+  fn should_refuse_anything_but_numbers() {
+    assert!("rolling".parse::<Version>().is_err());
+    assert!("22.04-lts".parse::<Version>().is_err());
+    assert!("".parse::<Version>().is_err());
+  }
+
+  #[test]
+  fn should_print_the_way_it_was_given() {
+    assert_eq!(v(" 22.04").to_string(), "22.04");
+  }
+}
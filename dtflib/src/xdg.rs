@@ -0,0 +1,12 @@
+use std::path::{Path, PathBuf};
+
+/// Directory where dtf keeps whatever it has to remember between runs,
+/// `$XDG_STATE_HOME/dtf` or `~/.local/state/dtf` if the variable is not set
+pub fn state_dir(home_dir: &Path) -> PathBuf {
+  let base = match std::env::var_os("XDG_STATE_HOME") {
+    Some(val) if PathBuf::from(&val).is_absolute() => PathBuf::from(val),
+    _ => home_dir.join(".local").join("state"),
+  };
+
+  base.join("dtf")
+}
@@ -0,0 +1 @@
+-- lua/options.lua
@@ -0,0 +1 @@
+-- plugin/packer.lua
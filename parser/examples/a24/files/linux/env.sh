@@ -0,0 +1 @@
+linux
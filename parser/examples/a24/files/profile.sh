@@ -0,0 +1 @@
+any
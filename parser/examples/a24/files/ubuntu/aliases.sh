@@ -0,0 +1 @@
+ubuntu
@@ -0,0 +1,265 @@
+use crate::{
+  config::{Block, Config, Expr, Section, Target},
+  Render, RenderState, Vars,
+};
+use dtflib::Context;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// Where an adopted file ends up in the repo and where it should be linked back to
+#[derive(Debug, PartialEq, Eq)]
+pub struct Adoption {
+  pub name: String,
+  pub src: PathBuf,
+  pub dst: PathBuf,
+}
+
+/// Writes the destination directory the way a human would, with `~` in place of the home dir
+fn render_to(cx: &Context, dir: &Path) -> String {
+  match dir.strip_prefix(cx.home_dir) {
+    Ok(rest) => match rest.to_str().unwrap_or("") {
+      "" => String::from("~/"),
+      rest => format!("~/{}", rest.replace('$', "$$")),
+    },
+    // a `$` of the path is no variable
+    Err(_) => dir.to_str().unwrap().replace('$', "$$"),
+  }
+}
+
+fn pick_section(
+  cx: &Context,
+  config: &Config,
+  target: Option<&Target>,
+  section: Option<usize>,
+) -> Result<Option<usize>> {
+  if let Some(n) = section {
+    return match config.map.get(n) {
+      Some(_) => Ok(Some(n)),
+      None => Err(Error::new(
+        ErrorKind::InvalidInput,
+        format!("there is no section #{} in the config", n),
+      )),
+    };
+  }
+
+  let found = config.map.iter().position(|section| match target {
+    Some(target) => section.target.contains(&Expr::Target(target.clone())),
+    None => section
+      .target
+      .iter()
+      .any(|expr| expr.eval(cx.client_os, cx.machine)),
+  });
+
+  Ok(found)
+}
+
+/// Appends a block for the file at `path` to the chosen (or a brand new) section,
+/// returns where the file should be moved to
+pub fn adopt(
+  cx: &Context,
+  config: &mut Config,
+  path: &Path,
+  target: Option<&str>,
+  section: Option<usize>,
+) -> Result<Adoption> {
+  let target = match target {
+    Some(val) => Some(
+      config
+        .target(val)
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?,
+    ),
+    None => None,
+  };
+
+  let name = match path.file_name().and_then(|name| name.to_str()) {
+    Some(name) => name.to_owned(),
+    None => {
+      return Err(Error::new(
+        ErrorKind::InvalidInput,
+        "path does not point to a file",
+      ))
+    }
+  };
+  let dst = path.parent().unwrap().to_path_buf();
+
+  let n = match pick_section(cx, config, target.as_ref(), section)? {
+    Some(n) => n,
+    None => {
+      config.map.push(Section {
+        target: match &target {
+          Some(target) => vec![Expr::Target(target.clone())],
+          None => Section::default_target(),
+        },
+        when: None,
+        files: vec![],
+        from: Section::default_from(),
+        mkdir: None,
+        dir_mode: None,
+      });
+      config.map.len() - 1
+    }
+  };
+
+  let section = &mut config.map[n];
+
+  let target = match target {
+    Some(target) => target,
+    None => match Target::pick(
+      cx.client_os,
+      cx.machine,
+      &Expr::select(&section.target, cx.client_os, cx.machine),
+    ) {
+      Some(target) => target,
+      None => {
+        return Err(Error::new(
+          ErrorKind::InvalidInput,
+          "the section is not meant for this OS, specify `--target` explicitly",
+        ))
+      }
+    },
+  };
+
+  let vars = Vars::new(cx, &config.vars).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+  let source_dir = target.dir();
+  let state = RenderState {
+    home_dir: cx.home_dir,
+    base_dir: cx.base_dir,
+    source_dir: &source_dir,
+    vars: &vars,
+  };
+
+  let src = Render::from(&section.from)
+    .render(&state)
+    .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("`from`: {}", e)))?;
+
+  section.files.push(Block {
+    name: name.clone(),
+    to: render_to(cx, &dst),
+    target: None,
+    when: None,
+    dst_name: None,
+    link: None,
+    relative: None,
+    recursive: None,
+    exclude: vec![],
+  });
+
+  Ok(Adoption { name, src, dst })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{adopt, Adoption};
+  use crate::config::Config;
+  use crate::read_file;
+  use dtflib::{client_os, Context};
+  use pretty_assertions::assert_eq;
+  use std::io;
+  use std::path::PathBuf;
+
+  fn base_dir(t: &str) -> PathBuf {
+    std::env::current_dir().unwrap().join("examples").join(t)
+  }
+
+  #[test]
+  fn should_append_to_the_section_of_the_given_target() -> io::Result<()> {
+    let base_dir = &base_dir("a08");
+    let home_dir = &PathBuf::from("/Users/gman");
+    let config_path = &base_dir.join("dotthefiles.yml");
+
+    let mut config: Config = read_file(config_path)?;
+
+    let cx = Context {
+      base_dir,
+      home_dir,
+      client_os: &client_os::Type::Macos.into(),
+      machine: &Default::default(),
+      config_path,
+      child: false,
+    };
+
+    let actual = adopt(
+      &cx,
+      &mut config,
+      &home_dir.join(".config/kitty/kitty.conf"),
+      Some("linux"),
+      None,
+    )?;
+
+    assert_eq!(
+      actual,
+      Adoption {
+        name: String::from("kitty.conf"),
+        src: base_dir.join("files/linux"),
+        dst: home_dir.join(".config/kitty"),
+      }
+    );
+
+    let block = config.map[0].files.last().unwrap();
+    assert_eq!(block.name, "kitty.conf");
+    assert_eq!(
+      block.to, "~/.config/kitty",
+      "should write the destination relative to the home dir"
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_add_a_new_section_when_no_section_fits() -> io::Result<()> {
+    let base_dir = &base_dir("a09");
+    let home_dir = &PathBuf::from("/Users/gman");
+    let config_path = &base_dir.join("dotthefiles.yml");
+
+    let mut config: Config = read_file(config_path)?;
+
+    let cx = Context {
+      base_dir,
+      home_dir,
+      client_os: &client_os::Type::Macos.into(),
+      machine: &Default::default(),
+      config_path,
+      child: false,
+    };
+
+    let actual = adopt(&cx, &mut config, &PathBuf::from("/etc/hosts"), None, None)?;
+
+    assert_eq!(
+      actual,
+      Adoption {
+        name: String::from("hosts"),
+        src: base_dir.join("files"),
+        dst: PathBuf::from("/etc"),
+      }
+    );
+
+    assert_eq!(config.map.len(), 2);
+    assert_eq!(config.map[1].files[0].to, "/etc");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_refuse_a_missing_section() -> io::Result<()> {
+    let base_dir = &base_dir("a09");
+    let home_dir = &PathBuf::from("/home/gman");
+    let config_path = &base_dir.join("dotthefiles.yml");
+
+    let mut config: Config = read_file(config_path)?;
+
+    let cx = Context {
+      base_dir,
+      home_dir,
+      client_os: &client_os::Type::Linux.into(),
+      machine: &Default::default(),
+      config_path,
+      child: false,
+    };
+
+    let actual = adopt(&cx, &mut config, &home_dir.join(".zshrc"), None, Some(3));
+
+    assert_eq!(actual.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+    Ok(())
+  }
+}
@@ -1,7 +1,35 @@
+use super::{Expr, When};
+use dtflib::LinkMode;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Block {
   pub name: String,
   pub to: String,
+
+  /// Targets of this block alone, the ones of the section otherwise
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub target: Option<Vec<Expr>>,
+
+  /// Conditions of this block, on top of the ones of its section
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub when: Option<When>,
+
+  /// Name of the file at the destination, the same as the `name` by default
+  #[serde(rename = "as", default, skip_serializing_if = "Option::is_none")]
+  pub dst_name: Option<String>,
+
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub link: Option<LinkMode>,
+
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub relative: Option<bool>,
+
+  /// Treats the name as a directory and links every file within it, one by one
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub recursive: Option<bool>,
+
+  /// Glob patterns of paths within a recursive block that are left out
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub exclude: Vec<String>,
 }
@@ -1,7 +1,77 @@
-use super::Section;
-use serde::{Deserialize, Serialize};
+use super::{custom, Definition, Section, Target};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Serialize)]
 pub struct Config {
+  /// Targets of one's own, by name, for the sections to refer to
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub targets: BTreeMap<String, Definition>,
+
+  /// Variables of one's own for the paths and one another to refer to, they win over any other
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub vars: BTreeMap<String, String>,
+
   pub map: Vec<Section>,
 }
+
+impl Config {
+  /// Reads a target the way the sections do, names of the `targets:` table included
+  pub fn target(&self, val: &str) -> Result<Target, String> {
+    match Target::from_str(val).map_err(|e| e.to_string())? {
+      Target::Named(name) => custom::resolve(&self.targets)?
+        .remove(&name)
+        .ok_or_else(|| format!("unknown target `{}`", name)),
+      target => Ok(target),
+    }
+  }
+
+  /// Swaps the names the sections and blocks refer to for the targets they stand for
+  fn resolve(&mut self) -> Result<(), String> {
+    let resolved = custom::resolve(&self.targets)?;
+    let mut lookup = |name: &str| {
+      resolved
+        .get(name)
+        .cloned()
+        .ok_or_else(|| format!("unknown target `{}`", name))
+    };
+
+    for section in &mut self.map {
+      for expr in &mut section.target {
+        expr.resolve(&mut lookup)?;
+      }
+
+      for block in &mut section.files {
+        for expr in block.target.iter_mut().flatten() {
+          expr.resolve(&mut lookup)?;
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+impl<'de> Deserialize<'de> for Config {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    #[derive(Deserialize)]
+    struct Raw {
+      #[serde(default)]
+      targets: BTreeMap<String, Definition>,
+      #[serde(default)]
+      vars: BTreeMap<String, String>,
+      map: Vec<Section>,
+    }
+
+    let raw = Raw::deserialize(deserializer)?;
+    let mut config = Config {
+      targets: raw.targets,
+      vars: raw.vars,
+      map: raw.map,
+    };
+
+    config.resolve().map_err(de::Error::custom)?;
+    Ok(config)
+  }
+}
@@ -0,0 +1,350 @@
+use super::expr::visit_op;
+use super::{Expr, Target};
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// A target of the `targets:` table as written, e.g. `workstation: [macos, fedora]`, or
+/// `{target: ..., dir: ..., weight: ...}` to pick the source directory and the weight
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Definition {
+  /// The target applies when any of these is true
+  pub target: Vec<Expr>,
+  /// Source directory, the name of the target by default
+  pub dir: Option<String>,
+  /// See `Target::weight`, the one of its weightiest target by default
+  pub weight: Option<u8>,
+}
+
+/// A target of the `targets:` table with the names within it resolved
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Custom {
+  pub name: String,
+  pub target: Vec<Expr>,
+  pub dir: String,
+  pub weight: u8,
+}
+
+/// Turns the definitions into targets, one by name, following the names they are made
+/// of. A name can't stand for a built-in target nor be defined in terms of itself
+pub fn resolve(defs: &BTreeMap<String, Definition>) -> Result<HashMap<String, Target>, String> {
+  let mut resolved = HashMap::new();
+
+  for name in defs.keys() {
+    resolve_one(name, defs, &mut resolved, &mut vec![])?;
+  }
+
+  Ok(resolved)
+}
+
+fn resolve_one(
+  name: &str,
+  defs: &BTreeMap<String, Definition>,
+  resolved: &mut HashMap<String, Target>,
+  stack: &mut Vec<String>,
+) -> Result<Target, String> {
+  if let Some(target) = resolved.get(name) {
+    return Ok(target.clone());
+  }
+
+  if stack.iter().any(|one| one == name) {
+    stack.push(name.to_owned());
+    return Err(format!(
+      "target `{}` is defined in terms of itself: {}",
+      name,
+      stack.join(" -> ")
+    ));
+  }
+
+  match Target::from_str(name) {
+    Ok(Target::Named(_)) => {}
+    Ok(_) => {
+      return Err(format!(
+        "`{}` is a built-in target, pick another name",
+        name
+      ))
+    }
+    Err(_) => {
+      return Err(format!(
+        "`{}` can't name a target, use letters, digits, `-` and `_`",
+        name
+      ))
+    }
+  }
+
+  let def = defs
+    .get(name)
+    .ok_or_else(|| format!("unknown target `{}`", name))?;
+
+  stack.push(name.to_owned());
+  let mut target = def.target.clone();
+  for expr in &mut target {
+    expr.resolve(&mut |name| resolve_one(name, defs, resolved, stack))?;
+  }
+  stack.pop();
+
+  let weight = def.weight.unwrap_or_else(|| {
+    target
+      .iter()
+      .flat_map(Expr::targets)
+      .map(Target::weight)
+      .min()
+      .unwrap_or_else(|| Target::Any.weight())
+  });
+
+  let custom = Target::Custom(Box::new(Custom {
+    name: name.to_owned(),
+    dir: def.dir.clone().unwrap_or_else(|| name.to_owned()),
+    target,
+    weight,
+  }));
+
+  resolved.insert(name.to_owned(), custom.clone());
+  Ok(custom)
+}
+
+struct DefinitionVisitor;
+
+impl<'de> Visitor<'de> for DefinitionVisitor {
+  type Value = Definition;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("targets like `macos || fedora`, a list of them, or `target`, `dir` and `weight`")
+  }
+
+  fn visit_str<E: de::Error>(self, val: &str) -> Result<Definition, E> {
+    Ok(Definition::from(vec![
+      Expr::from_str(val).map_err(E::custom)?
+    ]))
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Definition, A::Error> {
+    let mut target = vec![];
+
+    while let Some(expr) = seq.next_element()? {
+      target.push(expr);
+    }
+
+    Ok(Definition::from(target))
+  }
+
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Definition, A::Error> {
+    let mut key: String = map
+      .next_key()?
+      .ok_or_else(|| de::Error::custom("expected `target`, or one of `all`, `any` and `not`"))?;
+
+    if !matches!(key.as_str(), "target" | "dir" | "weight") {
+      return Ok(Definition::from(vec![visit_op(&key, map)?]));
+    }
+
+    let mut target = None;
+    let mut dir = None;
+    let mut weight = None;
+
+    loop {
+      match key.as_str() {
+        "target" => {
+          let def: Definition = map.next_value()?;
+          if def.dir.is_some() || def.weight.is_some() {
+            return Err(de::Error::custom(
+              "`target` can't have a `dir` or a `weight` of its own",
+            ));
+          }
+          target = Some(def.target);
+        }
+        "dir" => dir = Some(map.next_value()?),
+        "weight" => weight = Some(map.next_value()?),
+        key => {
+          return Err(de::Error::custom(format!(
+            "unknown field `{}`, expected one of `target`, `dir` and `weight`",
+            key
+          )))
+        }
+      }
+
+      key = match map.next_key()? {
+        Some(key) => key,
+        None => break,
+      };
+    }
+
+    Ok(Definition {
+      target: target.ok_or_else(|| de::Error::missing_field("target"))?,
+      dir,
+      weight,
+    })
+  }
+}
+
+impl From<Vec<Expr>> for Definition {
+  fn from(target: Vec<Expr>) -> Self {
+    Self {
+      target,
+      dir: None,
+      weight: None,
+    }
+  }
+}
+
+impl<'de> Deserialize<'de> for Definition {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    deserializer.deserialize_any(DefinitionVisitor)
+  }
+}
+
+impl Serialize for Definition {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    struct Target<'a>(&'a [Expr]);
+
+    impl Serialize for Target<'_> {
+      fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+          [expr] => expr.serialize(serializer),
+          exprs => exprs.serialize(serializer),
+        }
+      }
+    }
+
+    if self.dir.is_none() && self.weight.is_none() {
+      return Target(&self.target).serialize(serializer);
+    }
+
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry("target", &Target(&self.target))?;
+    if let Some(dir) = &self.dir {
+      map.serialize_entry("dir", dir)?;
+    }
+    if let Some(weight) = &self.weight {
+      map.serialize_entry("weight", weight)?;
+    }
+    map.end()
+  }
+}
+
+#[cfg(test)]
+mod test_custom {
+  use super::{resolve, Custom, Definition};
+  use crate::config::{Config, Expr, Target};
+  use dtflib::{client_os, Machine};
+  use pretty_assertions::assert_eq;
+  use std::collections::BTreeMap;
+  use std::str::FromStr;
+
+  fn defs(json: &str) -> BTreeMap<String, Definition> {
+    serde_json::from_str(json).unwrap()
+  }
+
+  #[test]
+  fn should_read_every_form() {
+    let actual = defs(
+      r#"{
+        "desk": "macos || fedora",
+        "workstation": ["macos", "fedora"],
+        "server": {"target": "(debian || ubuntu) && host:srv-*", "dir": "srv", "weight": 3},
+        "tiny": {"all": ["linux", "arch:aarch64"]}
+      }"#,
+    );
+
+    assert_eq!(
+      actual["workstation"],
+      Definition::from(vec![
+        Expr::from_str("macos").unwrap(),
+        Expr::from_str("fedora").unwrap()
+      ])
+    );
+    assert_eq!(actual["server"].dir.as_deref(), Some("srv"));
+    assert_eq!(actual["server"].weight, Some(3));
+    assert_eq!(
+      actual["tiny"].target,
+      vec![Expr::from_str("linux && arch:aarch64").unwrap()]
+    );
+
+    let err = serde_json::from_str::<Definition>(r#"{"dir": "x"}"#).unwrap_err();
+    assert!(err.to_string().contains("missing field `target`"));
+  }
+
+  #[test]
+  fn should_write_the_shortest_form_back() {
+    let actual =
+      defs(r#"{"a": ["macos"], "b": ["macos", "fedora"], "c": {"target": "linux", "dir": "x"}}"#);
+
+    assert_eq!(
+      serde_json::to_string(&actual).unwrap(),
+      r#"{"a":"macos","b":["macos","fedora"],"c":{"target":"linux","dir":"x"}}"#
+    );
+  }
+
+  #[test]
+  fn should_resolve_names_within_names() {
+    let resolved = resolve(&defs(
+      r#"{"server": "debian || ubuntu", "web": {"target": "server && host:web-*", "dir": "www"}}"#,
+    ))
+    .unwrap();
+
+    let web = match &resolved["web"] {
+      Target::Custom(custom) => custom,
+      target => panic!("{:?} is not a custom target", target),
+    };
+
+    assert_eq!(web.dir, "www");
+    assert_eq!(web.weight, Target::Host("web-*".to_owned()).weight());
+    assert_eq!(
+      web.target,
+      vec![Expr::All(vec![
+        Expr::Target(resolved["server"].clone()),
+        Expr::Target(Target::Host("web-*".to_owned())),
+      ])]
+    );
+
+    let machine = Machine {
+      host: "web-1".to_owned(),
+      ..Machine::default()
+    };
+    assert!(resolved["web"].is_compatible(&client_os::Type::Ubuntu.into(), &machine));
+    assert!(!resolved["web"].is_compatible(&client_os::Type::Fedora.into(), &machine));
+  }
+
+  #[test]
+  fn should_weight_like_the_weightiest_target() {
+    let resolved = resolve(&defs(r#"{"desk": ["macos", "ubuntu"]}"#)).unwrap();
+
+    assert_eq!(
+      resolved["desk"],
+      Target::Custom(Box::new(Custom {
+        name: "desk".to_owned(),
+        target: vec![Expr::Target(Target::Macos), Expr::Target(Target::Ubuntu)],
+        dir: "desk".to_owned(),
+        weight: Target::Ubuntu.weight(),
+      }))
+    );
+  }
+
+  #[test]
+  fn should_tell_what_is_wrong() {
+    assert_eq!(
+      resolve(&defs(r#"{"a": "b", "b": "linux || a"}"#)).unwrap_err(),
+      "target `a` is defined in terms of itself: a -> b -> a"
+    );
+    assert_eq!(
+      resolve(&defs(r#"{"a": "linux && plan9"}"#)).unwrap_err(),
+      "unknown target `plan9`"
+    );
+    assert_eq!(
+      resolve(&defs(r#"{"ubuntu": "debian"}"#)).unwrap_err(),
+      "`ubuntu` is a built-in target, pick another name"
+    );
+  }
+
+  #[test]
+  fn should_refuse_unknown_names_in_sections() {
+    let err = serde_json::from_str::<Config>(
+      r#"{"targets": {"desk": "macos"}, "map": [{"target": ["desk || plan9"], "files": []}]}"#,
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("unknown target `plan9`"));
+  }
+}
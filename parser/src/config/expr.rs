@@ -0,0 +1,496 @@
+use super::Target;
+use dtflib::{client_os, Machine};
+use serde::de::{self, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A target, or targets combined with `all`, `any` and `not`. In the string form they
+/// are written `&&`, `||` and `!`, e.g. `linux && !arch`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Expr {
+  Target(Target),
+  All(Vec<Expr>),
+  Any(Vec<Expr>),
+  Not(Box<Expr>),
+}
+
+impl Default for Expr {
+  fn default() -> Self {
+    Self::Target(Target::default())
+  }
+}
+
+impl Expr {
+  pub fn eval(&self, os: &client_os::Os, machine: &Machine) -> bool {
+    self.matched(os, machine).is_some()
+  }
+
+  /// The targets that made the expression true, those under a `not` aside, or `None`
+  /// when it is false
+  pub fn matched(&self, os: &client_os::Os, machine: &Machine) -> Option<Vec<Target>> {
+    match self {
+      Expr::Target(target) => match target.is_compatible(os, machine) {
+        true => Some(vec![target.clone()]),
+        false => None,
+      },
+      Expr::All(exprs) => {
+        let mut ret = vec![];
+
+        for expr in exprs {
+          ret.extend(expr.matched(os, machine)?);
+        }
+
+        Some(ret)
+      }
+      Expr::Any(exprs) => {
+        let matched: Vec<Vec<Target>> = exprs
+          .iter()
+          .filter_map(|expr| expr.matched(os, machine))
+          .collect();
+
+        match matched.is_empty() {
+          true => None,
+          false => Some(matched.into_iter().flatten().collect()),
+        }
+      }
+      Expr::Not(expr) => match expr.matched(os, machine) {
+        Some(_) => None,
+        None => Some(vec![]),
+      },
+    }
+  }
+
+  /// Targets of the expressions that are true, for `Target::pick` to weigh. An
+  /// expression made only of negations, like `!windows`, stands for `Any`
+  pub fn select(exprs: &[Expr], os: &client_os::Os, machine: &Machine) -> Vec<Target> {
+    let mut ret: Vec<Target> = vec![];
+
+    for matched in exprs.iter().filter_map(|expr| expr.matched(os, machine)) {
+      let matched = match matched.is_empty() {
+        true => vec![Target::Any],
+        false => matched,
+      };
+
+      for target in matched {
+        if !ret.contains(&target) {
+          ret.push(target);
+        }
+      }
+    }
+
+    ret
+  }
+
+  /// Targets the expression is made of, those under a `not` aside
+  pub fn targets(&self) -> Vec<&Target> {
+    match self {
+      Expr::Target(target) => vec![target],
+      Expr::All(exprs) | Expr::Any(exprs) => exprs.iter().flat_map(Expr::targets).collect(),
+      Expr::Not(_) => vec![],
+    }
+  }
+
+  /// Replaces the names of the `targets:` table with what `lookup` gives for them
+  pub(crate) fn resolve(
+    &mut self,
+    lookup: &mut dyn FnMut(&str) -> Result<Target, String>,
+  ) -> Result<(), String> {
+    match self {
+      Expr::Target(Target::Named(name)) => *self = Expr::Target(lookup(name)?),
+      Expr::Target(_) => {}
+      Expr::All(exprs) | Expr::Any(exprs) => {
+        for expr in exprs {
+          expr.resolve(lookup)?;
+        }
+      }
+      Expr::Not(expr) => expr.resolve(lookup)?,
+    }
+
+    Ok(())
+  }
+
+  /// Whether the string form can tell the expression, it has no words for empty lists
+  fn has_string_form(&self) -> bool {
+    match self {
+      Expr::Target(_) => true,
+      Expr::All(exprs) | Expr::Any(exprs) => {
+        !exprs.is_empty() && exprs.iter().all(Expr::has_string_form)
+      }
+      Expr::Not(expr) => expr.has_string_form(),
+    }
+  }
+}
+
+impl fmt::Display for Expr {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    // `||` binds the loosest, then `&&`, then `!`
+    let binds = |expr: &Expr| match expr {
+      Expr::Any(_) => 0,
+      Expr::All(_) => 1,
+      _ => 2,
+    };
+    let join = |f: &mut fmt::Formatter<'_>, exprs: &[Expr], op: &str, min: u8| {
+      for (i, expr) in exprs.iter().enumerate() {
+        if i > 0 {
+          write!(f, " {} ", op)?;
+        }
+        match binds(expr) < min {
+          true => write!(f, "({})", expr)?,
+          false => write!(f, "{}", expr)?,
+        }
+      }
+      Ok(())
+    };
+
+    match self {
+      Expr::Target(target) => write!(f, "{}", target.name()),
+      Expr::All(exprs) => join(f, exprs, "&&", 2),
+      Expr::Any(exprs) => join(f, exprs, "||", 1),
+      Expr::Not(expr) => match binds(expr) {
+        2 => write!(f, "!{}", expr),
+        _ => write!(f, "!({})", expr),
+      },
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+  And,
+  Or,
+  Not,
+  Open,
+  Close,
+  Atom(&'a str),
+}
+
+/// Splits the string form into tokens along with where they start. Anything between the
+/// operators is a target, spaces and all, so that `mac os` or `ubuntu >= 22.04` work
+fn tokenize(val: &str) -> Result<Vec<(usize, Token<'_>)>, String> {
+  let mut ret = vec![];
+  let mut i = 0;
+
+  while i < val.len() {
+    let rest = &val[i..];
+    let c = rest.chars().next().unwrap();
+
+    let (token, len) = match c {
+      c if c.is_whitespace() => {
+        i += c.len_utf8();
+        continue;
+      }
+      '(' => (Token::Open, 1),
+      ')' => (Token::Close, 1),
+      '!' => (Token::Not, 1),
+      '&' if rest.starts_with("&&") => (Token::And, 2),
+      '|' if rest.starts_with("||") => (Token::Or, 2),
+      '&' | '|' => {
+        return Err(format!(
+          "`{}` at {} in `{}` should be `{}{}`",
+          c, i, val, c, c
+        ))
+      }
+      _ => {
+        let len = rest.find(['(', ')', '&', '|']).unwrap_or(rest.len());
+        (Token::Atom(rest[..len].trim_end()), len)
+      }
+    };
+
+    ret.push((i, token));
+    i += len;
+  }
+
+  Ok(ret)
+}
+
+struct ExprParser<'a> {
+  src: &'a str,
+  tokens: Vec<(usize, Token<'a>)>,
+  pos: usize,
+}
+
+impl<'a> ExprParser<'a> {
+  fn peek(&self) -> Option<&Token<'a>> {
+    self.tokens.get(self.pos).map(|(_, token)| token)
+  }
+
+  fn unexpected(&self) -> String {
+    match self.tokens.get(self.pos) {
+      Some((at, _)) => format!(
+        "unexpected `{}` at {} in `{}`",
+        self.src[*at..].split_whitespace().next().unwrap_or(""),
+        at,
+        self.src
+      ),
+      None => format!("`{}` ends too early", self.src),
+    }
+  }
+
+  /// any := all ("||" all)*
+  fn any(&mut self) -> Result<Expr, String> {
+    let mut exprs = vec![self.all()?];
+
+    while self.peek() == Some(&Token::Or) {
+      self.pos += 1;
+      exprs.push(self.all()?);
+    }
+
+    Ok(match exprs.len() {
+      1 => exprs.pop().unwrap(),
+      _ => Expr::Any(exprs),
+    })
+  }
+
+  /// all := not ("&&" not)*
+  fn all(&mut self) -> Result<Expr, String> {
+    let mut exprs = vec![self.not()?];
+
+    while self.peek() == Some(&Token::And) {
+      self.pos += 1;
+      exprs.push(self.not()?);
+    }
+
+    Ok(match exprs.len() {
+      1 => exprs.pop().unwrap(),
+      _ => Expr::All(exprs),
+    })
+  }
+
+  /// not := "!" not | "(" any ")" | target
+  fn not(&mut self) -> Result<Expr, String> {
+    match self.peek() {
+      Some(Token::Not) => {
+        self.pos += 1;
+        Ok(Expr::Not(Box::new(self.not()?)))
+      }
+      Some(Token::Open) => {
+        self.pos += 1;
+        let expr = self.any()?;
+
+        match self.peek() {
+          Some(Token::Close) => {
+            self.pos += 1;
+            Ok(expr)
+          }
+          _ => Err(self.unexpected()),
+        }
+      }
+      Some(Token::Atom(val)) => {
+        let target = Target::from_str(val).map_err(|e| format!("{} in `{}`", e, self.src))?;
+        self.pos += 1;
+        Ok(Expr::Target(target))
+      }
+      _ => Err(self.unexpected()),
+    }
+  }
+}
+
+impl FromStr for Expr {
+  type Err = String;
+
+  fn from_str(val: &str) -> Result<Self, Self::Err> {
+    let mut parser = ExprParser {
+      src: val,
+      tokens: tokenize(val)?,
+      pos: 0,
+    };
+
+    let expr = parser.any()?;
+
+    match parser.peek() {
+      None => Ok(expr),
+      Some(_) => Err(parser.unexpected()),
+    }
+  }
+}
+
+struct ExprVisitor;
+
+impl<'de> Visitor<'de> for ExprVisitor {
+  type Value = Expr;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("a target like `linux && !arch`, or one of `all`, `any` and `not`")
+  }
+
+  fn visit_str<E: de::Error>(self, val: &str) -> Result<Expr, E> {
+    Expr::from_str(val).map_err(E::custom)
+  }
+
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Expr, A::Error> {
+    let op: String = map
+      .next_key()?
+      .ok_or_else(|| de::Error::custom("expected one of `all`, `any` and `not`"))?;
+
+    visit_op(&op, map)
+  }
+}
+
+/// Reads the rest of the structured form once its operator, the only key, is known
+pub(super) fn visit_op<'de, A: MapAccess<'de>>(op: &str, mut map: A) -> Result<Expr, A::Error> {
+  let expr = match op {
+    "all" => Expr::All(map.next_value()?),
+    "any" => Expr::Any(map.next_value()?),
+    "not" => Expr::Not(Box::new(map.next_value()?)),
+    op => {
+      return Err(de::Error::custom(format!(
+        "unknown operator `{}`, expected one of `all`, `any` and `not`",
+        op
+      )))
+    }
+  };
+
+  if let Some(op) = map.next_key::<String>()? {
+    return Err(de::Error::custom(format!(
+      "`{}` can't stand next to another operator, nest them instead",
+      op
+    )));
+  }
+
+  Ok(expr)
+}
+
+impl<'de> Deserialize<'de> for Expr {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    deserializer.deserialize_any(ExprVisitor)
+  }
+}
+
+impl Serialize for Expr {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    if self.has_string_form() {
+      return serializer.serialize_str(&self.to_string());
+    }
+
+    let mut map = serializer.serialize_map(Some(1))?;
+
+    match self {
+      Expr::All(exprs) => map.serialize_entry("all", exprs)?,
+      Expr::Any(exprs) => map.serialize_entry("any", exprs)?,
+      Expr::Not(expr) => map.serialize_entry("not", expr)?,
+      Expr::Target(target) => map.serialize_entry("any", &[target])?,
+    }
+
+    map.end()
+  }
+}
+
+#[cfg(test)]
+mod test_expr {
+  use super::{Expr, Target};
+  use dtflib::{client_os, Machine};
+  use pretty_assertions::assert_eq;
+  use std::str::FromStr;
+
+  fn t(val: &str) -> Expr {
+    Expr::Target(Target::from_str(val).unwrap())
+  }
+
+  #[test]
+  fn should_parse_the_string_form() {
+    assert_eq!(
+      Expr::from_str("linux && !arch").unwrap(),
+      Expr::All(vec![t("linux"), Expr::Not(Box::new(t("arch")))])
+    );
+    assert_eq!(
+      Expr::from_str("macos || linux && arch:aarch64").unwrap(),
+      Expr::Any(vec![
+        t("macos"),
+        Expr::All(vec![t("linux"), t("arch:aarch64")])
+      ])
+    );
+    assert_eq!(
+      Expr::from_str("!(mac os || ubuntu >= 22.04)").unwrap(),
+      Expr::Not(Box::new(Expr::Any(vec![t("macos"), t("ubuntu>=22.04")])))
+    );
+  }
+
+  #[test]
+  fn should_tell_what_is_wrong() {
+    assert_eq!(
+      Expr::from_str("linux & arch").unwrap_err(),
+      "`&` at 6 in `linux & arch` should be `&&`"
+    );
+    assert_eq!(
+      Expr::from_str("linux && ").unwrap_err(),
+      "`linux && ` ends too early"
+    );
+    assert_eq!(
+      Expr::from_str("(linux || macos").unwrap_err(),
+      "`(linux || macos` ends too early"
+    );
+    assert_eq!(
+      Expr::from_str("linux ) macos").unwrap_err(),
+      "unexpected `)` at 6 in `linux ) macos`"
+    );
+    assert_eq!(
+      Expr::from_str("linux && plan 9").unwrap_err(),
+      "unknown target `plan 9` in `linux && plan 9`"
+    );
+  }
+
+  #[test]
+  fn should_print_the_string_form_back() {
+    for val in [
+      "linux && !arch",
+      "macos || linux && arch:aarch64",
+      "(macos || linux) && !(arch || ubuntu)",
+      "host:work-*",
+    ] {
+      assert_eq!(Expr::from_str(val).unwrap().to_string(), val);
+    }
+  }
+
+  #[test]
+  fn should_read_the_structured_form() {
+    let actual: Vec<Expr> =
+      serde_json::from_str(r#"[{"all": ["macos", {"not": "arch:x86_64"}]}, "!windows"]"#).unwrap();
+
+    assert_eq!(
+      actual,
+      vec![
+        Expr::All(vec![t("macos"), Expr::Not(Box::new(t("arch:x86_64")))]),
+        Expr::Not(Box::new(t("windows"))),
+      ]
+    );
+
+    let err = serde_json::from_str::<Expr>(r#"{"xor": ["macos"]}"#).unwrap_err();
+    assert!(err.to_string().contains("unknown operator `xor`"));
+  }
+
+  #[test]
+  fn should_select_the_targets_that_made_it_true() {
+    let os = &client_os::Type::Ubuntu.into();
+    let machine = &Machine {
+      arch: "aarch64".to_owned(),
+      ..Machine::default()
+    };
+
+    let exprs = vec![
+      Expr::from_str("linux && !arch").unwrap(),
+      Expr::from_str("ubuntu && arch:aarch64").unwrap(),
+      Expr::from_str("macos").unwrap(),
+      Expr::from_str("!windows").unwrap(),
+    ];
+
+    assert_eq!(
+      Expr::select(&exprs, os, machine),
+      vec![
+        Target::Linux,
+        Target::Ubuntu,
+        Target::Cpu("aarch64".to_owned()),
+        Target::Any
+      ]
+    );
+    assert_eq!(
+      Target::pick(os, machine, &Expr::select(&exprs, os, machine)),
+      Some(Target::Cpu("aarch64".to_owned()))
+    );
+    assert_eq!(
+      Target::pick(os, machine, &Expr::select(&exprs[..2], os, machine)),
+      Some(Target::Cpu("aarch64".to_owned()))
+    );
+  }
+}
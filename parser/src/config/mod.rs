@@ -9,3 +9,12 @@ pub use section::Section;
 
 mod target;
 pub use target::Target;
+
+mod expr;
+pub use expr::Expr;
+
+mod when;
+pub use when::When;
+
+mod custom;
+pub use custom::{Custom, Definition};
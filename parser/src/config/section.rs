@@ -1,23 +1,67 @@
-use super::{Block, Target};
-use serde::{Deserialize, Serialize};
+use super::{Block, Expr, When};
+use dtflib::dotfile::parse_mode;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Section {
+  /// The section applies when any of these is true
   #[serde(default = "Section::default_target")]
-  pub target: Vec<Target>,
+  pub target: Vec<Expr>,
+
+  /// Conditions the section applies under on top of its targets
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub when: Option<When>,
 
   pub files: Vec<Block>,
 
   #[serde(default = "Section::default_from")]
   pub from: String,
+
+  /// Creates missing destination directories of the section files
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub mkdir: Option<bool>,
+
+  /// Permissions of the created destination directories, in octal
+  #[serde(
+    default,
+    skip_serializing_if = "Option::is_none",
+    deserialize_with = "de_mode",
+    serialize_with = "ser_mode"
+  )]
+  pub dir_mode: Option<u32>,
 }
 
 impl Section {
-  fn default_target() -> Vec<Target> {
-    vec![Target::default()]
+  pub(crate) fn default_target() -> Vec<Expr> {
+    vec![Expr::default()]
   }
 
-  fn default_from() -> String {
+  pub(crate) fn default_from() -> String {
     String::from("files/$TARGET")
   }
 }
+
+/// Takes the mode either as a string or as a number made of octal digits, i.e. `755`
+fn de_mode<'de, D: Deserializer<'de>>(de: D) -> Result<Option<u32>, D::Error> {
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Raw {
+    Str(String),
+    Num(u64),
+  }
+
+  match Option::<Raw>::deserialize(de)? {
+    None => Ok(None),
+    Some(Raw::Str(val)) => parse_mode(&val).map(Some).map_err(de::Error::custom),
+    Some(Raw::Num(val)) => parse_mode(&val.to_string())
+      .map(Some)
+      .map_err(de::Error::custom),
+  }
+}
+
+fn ser_mode<S: Serializer>(mode: &Option<u32>, ser: S) -> Result<S::Ok, S::Error> {
+  match mode {
+    Some(mode) => ser.serialize_str(&format!("{:o}", mode)),
+    None => ser.serialize_none(),
+  }
+}
@@ -1,156 +1,224 @@
-use dtflib::client_os;
-use serde::{Deserialize, Serialize};
+use super::Custom;
+use dtflib::{client_os, machine, Machine, Version};
+use serde::de::{self, value};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Op {
+  Eq,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+}
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Clone, Ord, Serialize)]
-pub enum Target {
-  #[serde(alias = "linux")]
-  Linux,
-
-  #[serde(alias = "alpine")]
-  Alpine,
-
-  #[serde(alias = "amazon")]
-  Amazon,
-
-  #[serde(alias = "arch")]
-  Arch,
-
-  #[serde(alias = "centos")]
-  Centos,
-
-  #[serde(alias = "debian")]
-  Debian,
-
-  #[serde(alias = "fedora")]
-  Fedora,
-
-  #[serde(alias = "manjaro")]
-  Manjaro,
-
-  #[serde(alias = "opensuse")]
-  OpenSUSE,
-
-  #[serde(alias = "oraclelinux")]
-  OracleLinux,
-
-  #[serde(alias = "pop")]
-  Pop,
-
-  #[serde(alias = "redhat")]
-  Redhat,
+/// Range of versions a target is limited to, e.g. the `>=22.04` of `ubuntu>=22.04`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Constraint {
+  pub op: Op,
+  pub version: Version,
+}
 
-  #[serde(alias = "redhatenterprise", alias = "redhat enterprise")]
-  RedHatEnterprise,
+impl Constraint {
+  pub fn matches(&self, version: &Version) -> bool {
+    match self.op {
+      Op::Eq => version == &self.version,
+      Op::Lt => version < &self.version,
+      Op::Le => version <= &self.version,
+      Op::Gt => version > &self.version,
+      Op::Ge => version >= &self.version,
+    }
+  }
+}
 
-  #[serde(alias = "redox")]
-  Redox,
+impl fmt::Display for Constraint {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let op = match self.op {
+      Op::Eq => "=",
+      Op::Lt => "<",
+      Op::Le => "<=",
+      Op::Gt => ">",
+      Op::Ge => ">=",
+    };
+
+    write!(f, "{}{}", op, self.version)
+  }
+}
 
-  #[serde(alias = "solus")]
-  Solus,
+impl FromStr for Constraint {
+  type Err = String;
+
+  fn from_str(val: &str) -> Result<Self, Self::Err> {
+    // the longest operators go first, so that `>=` is not taken for `>`
+    let ops = [
+      (">=", Op::Ge),
+      ("<=", Op::Le),
+      ("==", Op::Eq),
+      (">", Op::Gt),
+      ("<", Op::Lt),
+      ("=", Op::Eq),
+    ];
 
-  #[serde(alias = "suse")]
-  SUSE,
+    let (op, rest) = ops
+      .iter()
+      .find_map(|(prefix, op)| val.strip_prefix(prefix).map(|rest| (*op, rest)))
+      .ok_or_else(|| format!("`{}` is not a version constraint", val))?;
 
-  #[serde(alias = "ubuntu")]
-  Ubuntu,
+    Ok(Self {
+      op,
+      version: rest.parse()?,
+    })
+  }
+}
 
-  #[serde(alias = "darwin", alias = "macos", alias = "mac os")]
-  Macos,
+macro_rules! define_target {
+  ($($(#[$doc:meta])* $variant:ident $dir:literal [$($alias:literal),*] ($($parent:ident)?),)*) => {
+    /// A system, a family of systems, `Any` of them, or a single machine
+    #[derive(Debug, Default, PartialEq, Eq, Clone)]
+    pub enum Target {
+      $($(#[$doc])* $variant,)*
+      #[default]
+      Any,
+      /// A system within a range of its versions, e.g. `ubuntu>=22.04`
+      Versioned(client_os::Type, Constraint),
+      /// Machines whose hostname matches the glob, e.g. `host:work-*`
+      Host(String),
+      /// Whoever runs dtf, e.g. `user:root`
+      User(String),
+      /// CPU architecture, e.g. `arch:aarch64`, not to be mixed up with Arch Linux
+      Cpu(String),
+      /// A target of the `targets:` table, by its name alone until the config resolves it
+      Named(String),
+      /// A target of the `targets:` table
+      Custom(Box<Custom>),
+    }
 
-  #[serde(alias = "win", alias = "windows")]
-  Windows,
+    impl Target {
+      /// The system the target stands for, `None` for `Any`
+      pub fn os(&self) -> Option<client_os::Type> {
+        match self {
+          $(Target::$variant => Some(client_os::Type::$variant),)*
+          Target::Versioned(os, _) => Some(*os),
+          Target::Any
+          | Target::Host(_)
+          | Target::User(_)
+          | Target::Cpu(_)
+          | Target::Named(_)
+          | Target::Custom(_) => None,
+        }
+      }
 
-  #[serde(
-    alias = "*",
-    alias = "any",
-    alias = "all",
-    alias = "every",
-    alias = "each"
-  )]
-  Any,
+      /// The target of the system, `None` for the unknown one
+      pub fn of(os: client_os::Type) -> Option<Target> {
+        match os {
+          $(client_os::Type::$variant => Some(Target::$variant),)*
+          client_os::Type::Unknown => None,
+        }
+      }
+    }
+  };
 }
 
-impl Default for Target {
-  fn default() -> Self {
-    Self::Any
-  }
-}
+dtflib::with_systems!(define_target);
 
 impl Target {
   /// Weights are ordered like this:
-  /// - `Any` (weights the most)
-  /// - all linux distros
-  /// - `Linux`, `Windows`, `Macos`
+  /// - hosts, users, architectures, in that order (weight the most)
+  /// - the targets of the `targets:` table go where their `weight` puts them
+  /// - the systems, the deeper in their family the more they weight,
+  ///   with a version constraint a system weights more than without one
+  /// - `Linux`, `Bsd`, `Windows`, `Macos`
+  /// - `Any`, the fallback for whatever nothing else suits
   ///
   /// `Linux` should weight less than its distros because if target list
   /// contains e.g. `Linux` and `Ubuntu`, then `Ubuntu` should be picked
-  /// rather than Linux.
+  /// rather than Linux. The same goes for `Debian` and `Ubuntu`, and for
+  /// `Ubuntu` and `ubuntu>=22.04`.
   ///
   /// Smallest -> More weight
   pub fn weight(&self) -> u8 {
+    let deepest = client_os::Type::all()
+      .iter()
+      .map(|one| one.depth())
+      .max()
+      .unwrap_or(0);
+
+    match (self, self.os()) {
+      (Target::Host(_), _) => 2,
+      (Target::User(_), _) => 3,
+      (Target::Cpu(_), _) => 4,
+      (Target::Custom(custom), _) => custom.weight,
+      (Target::Named(_), _) => u8::MAX,
+      (Target::Versioned(..), Some(os)) => 5 + 2 * (deepest - os.depth()),
+      (_, Some(os)) => 6 + 2 * (deepest - os.depth()),
+      (_, None) => u8::MAX - 1,
+    }
+  }
+
+  /// Returns the default directory name appropriate for the target, machines get theirs
+  /// under `host/`, `user/` and `arch/`
+  pub fn dir(&self) -> Cow<'_, str> {
     match self {
-      Target::Any => 1,
-
-      Target::Alpine
-      | Target::Amazon
-      | Target::Arch
-      | Target::Centos
-      | Target::Debian
-      | Target::Fedora
-      | Target::Manjaro
-      | Target::OpenSUSE
-      | Target::OracleLinux
-      | Target::Pop
-      | Target::Redhat
-      | Target::RedHatEnterprise
-      | Target::Redox
-      | Target::Solus
-      | Target::SUSE
-      | Target::Ubuntu => 2,
-
-      Target::Linux | Target::Macos | Target::Windows => 3,
+      Target::Host(val) => Cow::Owned(format!("host/{}", val)),
+      Target::User(val) => Cow::Owned(format!("user/{}", val)),
+      Target::Cpu(val) => Cow::Owned(format!("arch/{}", val)),
+      Target::Named(name) => Cow::Borrowed(name),
+      Target::Custom(custom) => Cow::Borrowed(&custom.dir),
+      target => match target.os() {
+        Some(os) => Cow::Borrowed(os.dir()),
+        None => Cow::Borrowed(""),
+      },
     }
   }
 
-  /// Returns the default directory name appropriate for the target
-  pub fn dir(&self) -> &str {
+  /// Canonical name of the target, the one it is written back into the config with
+  pub fn name(&self) -> Cow<'_, str> {
     match self {
-      Target::Macos => "macos",
-      Target::Linux => "linux",
-      Target::Windows => "windows",
-      Target::Alpine => "alpine",
-      Target::Amazon => "amazon",
-      Target::Arch => "arch",
-      Target::Centos => "centos",
-      Target::Debian => "debian",
-      Target::Fedora => "fedora",
-      Target::Manjaro => "manjaro",
-      Target::OpenSUSE => "opensuse",
-      Target::OracleLinux => "oraclelinux",
-      Target::Pop => "pop",
-      Target::Redhat => "redhat",
-      Target::RedHatEnterprise => "redhatenterprise",
-      Target::Redox => "redox",
-      Target::Solus => "solus",
-      Target::SUSE => "suse",
-      Target::Ubuntu => "ubuntu",
-      Target::Any => "",
+      Target::Any => Cow::Borrowed("any"),
+      Target::Versioned(os, constraint) => Cow::Owned(format!("{}{}", os.dir(), constraint)),
+      Target::Host(val) => Cow::Owned(format!("host:{}", val)),
+      Target::User(val) => Cow::Owned(format!("user:{}", val)),
+      Target::Cpu(val) => Cow::Owned(format!("arch:{}", val)),
+      Target::Named(name) => Cow::Borrowed(name),
+      Target::Custom(custom) => Cow::Borrowed(&custom.name),
+      target => target.dir(),
     }
   }
 
-  /// Given that we know the current OS, we should pick the right target out of many,
-  /// or return `None` if there is no good enough candidate
-  pub fn pick<'a>(os: &'a client_os::Type, targets: &'a [Target]) -> Option<Target> {
+  /// Where the target stands among the ones that suit the `os`, the most specific first:
+  /// by `weight`, except that a system the `os` is only like, as its os-release tells,
+  /// comes after every system it is or belongs to, though still before `Any`
+  fn rank(&self, os: &client_os::Os) -> (u8, u8) {
+    let tier = match (self, self.os()) {
+      (Target::Any, _) => 2,
+      (_, Some(target)) if !os.kind.ancestors().contains(&target) => 1,
+      _ => 0,
+    };
+
+    (tier, self.weight())
+  }
+
+  /// Given that we know the current OS and machine, we should pick the right target out
+  /// of many, or return `None` if there is no good enough candidate. Out of equally
+  /// weighted targets the one listed first wins
+  pub fn pick<'a>(
+    os: &'a client_os::Os,
+    machine: &'a Machine,
+    targets: &'a [Target],
+  ) -> Option<Target> {
     if targets.is_empty() {
       return None;
     }
 
     let mut candidates = targets.to_vec();
-    candidates.sort_unstable();
+    candidates.sort_by_key(|one| one.rank(os));
 
     for candidate in &candidates {
-      if candidate.is_compatible(os) {
+      if candidate.is_compatible(os, machine) {
         return Some(candidate.clone());
       }
     }
@@ -158,40 +226,127 @@ impl Target {
     None
   }
 
-  pub fn is_compatible(&self, os: &client_os::Type) -> bool {
-    use client_os::Type as Os;
-
-    match (self, os) {
-      (Target::Any, _) => true,
-      (Target::Windows, Os::Windows) => true,
-      (Target::Macos, Os::Macos) => true,
-      (Target::Alpine, Os::Alpine) => true,
-      (Target::Amazon, Os::Amazon) => true,
-      (Target::Arch, Os::Arch) => true,
-      (Target::Centos, Os::Centos) => true,
-      (Target::Debian, Os::Debian) => true,
-      (Target::Fedora, Os::Fedora) => true,
-      (Target::Manjaro, Os::Manjaro) => true,
-      (Target::OpenSUSE, Os::OpenSUSE) => true,
-      (Target::OracleLinux, Os::OracleLinux) => true,
-      (Target::Pop, Os::Pop) => true,
-      (Target::Redhat, Os::Redhat) => true,
-      (Target::RedHatEnterprise, Os::RedHatEnterprise) => true,
-      (Target::Redox, Os::Redox) => true,
-      (Target::Solus, Os::Solus) => true,
-      (Target::SUSE, Os::SUSE) => true,
-      (Target::Ubuntu, Os::Ubuntu) => true,
-
-      (Target::Linux, _) => match os {
-        Os::Macos | Os::Windows | Os::Unknown => false,
-        _ => true,
-      },
+  /// Every target out of many that suits the current OS, from the most specific one to
+  /// the least, the way `pick` ranks them. That is the order the source directories of a
+  /// file are looked through in
+  pub fn fallbacks(os: &client_os::Os, machine: &Machine, targets: &[Target]) -> Vec<Target> {
+    let mut candidates: Vec<Target> = vec![];
+
+    for target in targets {
+      if target.is_compatible(os, machine) && !candidates.contains(target) {
+        candidates.push(target.clone());
+      }
+    }
+
+    candidates.sort_by_key(|one| one.rank(os));
+
+    candidates
+  }
+
+  /// Whether the OS is the target, belongs to it, or is derived from it after its
+  /// os-release, so that e.g. Linux Mint suits `ubuntu`, `debian-family` and `linux`.
+  /// A version only says something about the very system it is the version of, so a
+  /// version constraint needs the same system, and a known version of it
+  pub fn is_compatible(&self, os: &client_os::Os, machine: &Machine) -> bool {
+    match self {
+      Target::Host(val) => {
+        return glob::Pattern::new(val).is_ok_and(|pattern| pattern.matches(&machine.host))
+      }
+      Target::User(val) => return val == &machine.user,
+      Target::Cpu(val) => {
+        return machine::canonical_arch(val) == machine::canonical_arch(&machine.arch)
+      }
+      Target::Custom(custom) => return custom.target.iter().any(|e| e.eval(os, machine)),
+      Target::Named(_) => return false,
+      _ => {}
+    }
+
+    let target = match self.os() {
+      Some(target) => target,
+      None => return true,
+    };
+
+    match self {
+      Target::Versioned(_, constraint) => {
+        os.kind == target && os.version.as_ref().is_some_and(|v| constraint.matches(v))
+      }
+      _ => std::iter::once(&os.kind)
+        .chain(&os.like)
+        .any(|one| one.ancestors().contains(&target)),
+    }
+  }
+}
+
+impl FromStr for Target {
+  type Err = value::Error;
 
-      _ => false,
+  fn from_str(val: &str) -> Result<Self, Self::Err> {
+    let unknown = || de::Error::custom(format!("unknown target `{}`", val));
+
+    if let Some((kind, rest)) = val.split_once(':') {
+      let rest = rest.trim();
+
+      if rest.is_empty() {
+        return Err(de::Error::custom(format!("`{}` names nothing", val)));
+      }
+
+      return match kind.trim() {
+        "host" => match glob::Pattern::new(rest) {
+          Ok(_) => Ok(Target::Host(rest.to_owned())),
+          Err(e) => Err(de::Error::custom(format!(
+            "`{}` is not a glob: {}",
+            rest, e
+          ))),
+        },
+        "user" => Ok(Target::User(rest.to_owned())),
+        "arch" => Ok(Target::Cpu(rest.to_owned())),
+        _ => Err(unknown()),
+      };
+    }
+
+    // `ubuntu>=22.04`, the name ends where the operator starts
+    if let Some(i) = val.find(['<', '>', '=']) {
+      let os = client_os::Type::from(val[..i].trim());
+      let constraint: Constraint = val[i..].trim().parse().map_err(de::Error::custom)?;
+
+      return match os {
+        client_os::Type::Unknown => Err(unknown()),
+        os => Ok(Target::Versioned(os, constraint)),
+      };
+    }
+
+    let is_name = |val: &str| {
+      !val.is_empty()
+        && val
+          .chars()
+          .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+
+    // a name that is none of ours is left for the `targets:` table of the config
+    match val {
+      "*" | "any" | "all" | "every" | "each" => Ok(Target::Any),
+      val => match Target::of(client_os::Type::from(val)) {
+        Some(target) => Ok(target),
+        None if is_name(val) => Ok(Target::Named(val.to_owned())),
+        None => Err(unknown()),
+      },
     }
   }
 }
 
+impl<'de> Deserialize<'de> for Target {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let val = String::deserialize(deserializer)?;
+    Target::from_str(&val).map_err(de::Error::custom)
+  }
+}
+
+impl Serialize for Target {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.name())
+  }
+}
+
 impl PartialOrd for Target {
   fn partial_cmp(&self, other: &Target) -> Option<Ordering> {
     let sw = self.weight();
@@ -205,6 +360,7 @@ impl PartialOrd for Target {
 mod test_is_compatible_with {
   use super::client_os;
   use super::Target;
+  use dtflib::Machine;
   use pretty_assertions::assert_eq;
 
   #[test]
@@ -213,7 +369,7 @@ mod test_is_compatible_with {
     let all_os = client_os::Type::all();
 
     for os in all_os {
-      let is_compatible = target.is_compatible(os);
+      let is_compatible = target.is_compatible(&(*os).into(), &Machine::default());
       if !is_compatible {
         println!("os {:?} should be compatible with {:?}", os, target);
       }
@@ -246,7 +402,7 @@ mod test_is_compatible_with {
     ];
 
     for (target, os) in pairs {
-      let is_compatible = target.is_compatible(os);
+      let is_compatible = target.is_compatible(&(*os).into(), &Machine::default());
       if !is_compatible {
         println!(
           "target `{:?}` should be compatible with os `{:?}`",
@@ -279,7 +435,7 @@ mod test_is_compatible_with {
     ];
 
     for (target, os) in pairs {
-      let is_compatible = target.is_compatible(os);
+      let is_compatible = target.is_compatible(&(*os).into(), &Machine::default());
       if !is_compatible {
         println!(
           "target `{:?}` should be compatible with os `{:?}`",
@@ -299,7 +455,7 @@ mod test_is_compatible_with {
     ];
 
     for (target, os) in pairs {
-      let is_compatible = target.is_compatible(os);
+      let is_compatible = target.is_compatible(&(*os).into(), &Machine::default());
       if is_compatible {
         println!(
           "target `{:?}` should not be compatible with os `{:?}`",
@@ -309,12 +465,366 @@ mod test_is_compatible_with {
       assert_eq!(is_compatible, false);
     }
   }
+
+  #[test]
+  fn distros_should_be_compatible_with_the_ones_they_are_like() {
+    let mint = client_os::Os::from(dtflib::OsRelease::parse(
+      "ID=linuxmint\nID_LIKE=\"ubuntu debian\"\n",
+    ));
+
+    assert_eq!(
+      Target::Ubuntu.is_compatible(&mint, &Machine::default()),
+      true
+    );
+    assert_eq!(
+      Target::Debian.is_compatible(&mint, &Machine::default()),
+      true
+    );
+    assert_eq!(
+      Target::Linux.is_compatible(&mint, &Machine::default()),
+      true
+    );
+    assert_eq!(
+      Target::Arch.is_compatible(&mint, &Machine::default()),
+      false
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_families {
+  use super::client_os;
+  use super::Target;
+  use dtflib::Machine;
+  use pretty_assertions::assert_eq;
+  use std::str::FromStr;
+
+  #[test]
+  fn systems_should_be_compatible_with_their_families() {
+    let pairs = &[
+      (Target::DebianFamily, client_os::Type::Mint),
+      (Target::Ubuntu, client_os::Type::Elementary),
+      (Target::Debian, client_os::Type::Kali),
+      (Target::RhelFamily, client_os::Type::Rocky),
+      (Target::RedHatEnterprise, client_os::Type::Alma),
+      (Target::Arch, client_os::Type::EndeavourOS),
+      (Target::OpenSUSE, client_os::Type::Tumbleweed),
+      (Target::Bsd, client_os::Type::OpenBSD),
+      (Target::Linux, client_os::Type::NixOS),
+    ];
+
+    for (target, os) in pairs {
+      assert!(
+        target.is_compatible(&(*os).into(), &Machine::default()),
+        "target `{:?}` should be compatible with os `{:?}`",
+        target,
+        os
+      );
+    }
+  }
+
+  #[test]
+  fn families_should_not_mix() {
+    let pairs = &[
+      (Target::Linux, client_os::Type::FreeBSD),
+      (Target::DebianFamily, client_os::Type::Fedora),
+      (Target::Ubuntu, client_os::Type::Debian),
+      (Target::Bsd, client_os::Type::Macos),
+    ];
+
+    for (target, os) in pairs {
+      assert!(
+        !target.is_compatible(&(*os).into(), &Machine::default()),
+        "target `{:?}` should not be compatible with os `{:?}`",
+        target,
+        os
+      );
+    }
+  }
+
+  #[test]
+  fn should_pick_the_closest_family() {
+    let targets = &[Target::Linux, Target::DebianFamily, Target::Debian];
+
+    assert_eq!(
+      Target::pick(&client_os::Type::Mint.into(), &Machine::default(), targets),
+      Some(Target::Debian)
+    );
+    assert_eq!(
+      Target::pick(
+        &client_os::Type::Fedora.into(),
+        &Machine::default(),
+        targets
+      ),
+      Some(Target::Linux)
+    );
+  }
+
+  #[test]
+  fn should_rank_what_the_system_is_above_what_it_is_like() {
+    // Rocky Linux tells it is like `rhel centos fedora`
+    let os = &client_os::Os {
+      like: vec![
+        client_os::Type::RedHatEnterprise,
+        client_os::Type::Centos,
+        client_os::Type::Fedora,
+      ],
+      ..client_os::Type::Rocky.into()
+    };
+    let targets = &[Target::Fedora, Target::Any, Target::RhelFamily];
+
+    assert_eq!(
+      Target::pick(os, &Machine::default(), targets),
+      Some(Target::RhelFamily)
+    );
+    assert_eq!(
+      Target::fallbacks(os, &Machine::default(), targets),
+      vec![Target::RhelFamily, Target::Fedora, Target::Any]
+    );
+  }
+
+  #[test]
+  fn should_read_family_names() {
+    assert_eq!(
+      Target::from_str("debian-family").unwrap(),
+      Target::DebianFamily
+    );
+    assert_eq!(Target::from_str("bsd").unwrap(), Target::Bsd);
+    assert_eq!(Target::from_str("rhel").unwrap(), Target::RedHatEnterprise);
+    assert_eq!(
+      Target::from_str("plan9").unwrap(),
+      Target::Named("plan9".to_owned())
+    );
+    assert!(Target::from_str("plan 9").is_err());
+  }
+}
+
+#[cfg(test)]
+mod test_versions {
+  use super::client_os;
+  use super::{Constraint, Op, Target};
+  use dtflib::Machine;
+  use pretty_assertions::assert_eq;
+  use std::str::FromStr;
+
+  fn os(kind: client_os::Type, version: Option<&str>) -> client_os::Os {
+    client_os::Os {
+      version: version.map(|val| val.parse().unwrap()),
+      ..kind.into()
+    }
+  }
+
+  #[test]
+  fn should_read_targets_with_a_version() {
+    assert_eq!(
+      Target::from_str("ubuntu>=22.04").unwrap(),
+      Target::Versioned(
+        client_os::Type::Ubuntu,
+        Constraint {
+          op: Op::Ge,
+          version: "22.04".parse().unwrap(),
+        }
+      )
+    );
+    assert_eq!(Target::from_str("macos < 14").unwrap().name(), "macos<14");
+    assert_eq!(Target::from_str("fedora=39").unwrap().name(), "fedora=39");
+    assert_eq!(Target::from_str("fedora==39").unwrap().name(), "fedora=39");
+    assert!(Target::from_str("ubuntu>=jammy").is_err());
+    assert!(Target::from_str("plan9>1").is_err());
+  }
+
+  #[test]
+  fn should_meet_the_constraint_with_the_version_of_the_os() {
+    let target = Target::from_str("ubuntu>=22.04").unwrap();
+
+    assert!(target.is_compatible(
+      &os(client_os::Type::Ubuntu, Some("22.04")),
+      &Machine::default()
+    ));
+    assert!(target.is_compatible(
+      &os(client_os::Type::Ubuntu, Some("24.04")),
+      &Machine::default()
+    ));
+    assert!(!target.is_compatible(
+      &os(client_os::Type::Ubuntu, Some("20.04")),
+      &Machine::default()
+    ));
+    assert!(!target.is_compatible(&os(client_os::Type::Ubuntu, None), &Machine::default()));
+    assert!(!target.is_compatible(
+      &os(client_os::Type::Mint, Some("22.1")),
+      &Machine::default()
+    ));
+  }
+
+  #[test]
+  fn should_pick_the_versioned_target_over_the_bare_one() {
+    let targets = &[
+      Target::Linux,
+      Target::Ubuntu,
+      Target::from_str("ubuntu<22.04").unwrap(),
+      Target::from_str("ubuntu>=22.04").unwrap(),
+    ];
+
+    assert_eq!(
+      Target::pick(
+        &os(client_os::Type::Ubuntu, Some("24.04")),
+        &Machine::default(),
+        targets
+      ),
+      Some(targets[3].clone())
+    );
+    assert_eq!(
+      Target::pick(
+        &os(client_os::Type::Ubuntu, Some("20.04")),
+        &Machine::default(),
+        targets
+      ),
+      Some(targets[2].clone())
+    );
+    assert_eq!(
+      Target::pick(
+        &os(client_os::Type::Ubuntu, None),
+        &Machine::default(),
+        targets
+      ),
+      Some(Target::Ubuntu)
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_machines {
+  use super::client_os;
+  use super::Target;
+  use dtflib::Machine;
+  use pretty_assertions::assert_eq;
+  use std::str::FromStr;
+
+  fn laptop() -> Machine {
+    Machine {
+      host: "work-laptop".to_owned(),
+      user: "gman".to_owned(),
+      arch: "aarch64".to_owned(),
+    }
+  }
+
+  #[test]
+  fn should_read_machine_targets() {
+    assert_eq!(
+      Target::from_str("host:work-*").unwrap(),
+      Target::Host("work-*".to_owned())
+    );
+    assert_eq!(
+      Target::from_str("user: root").unwrap(),
+      Target::User("root".to_owned())
+    );
+    assert_eq!(
+      Target::from_str("arch:aarch64").unwrap(),
+      Target::Cpu("aarch64".to_owned())
+    );
+    assert_eq!(Target::from_str("arch").unwrap(), Target::Arch);
+    assert!(Target::from_str("host:").is_err());
+    assert!(Target::from_str("planet:earth").is_err());
+  }
+
+  #[test]
+  fn should_match_the_machine() {
+    let os = &client_os::Type::Macos.into();
+    let machine = &laptop();
+
+    let matching = ["host:work-*", "user:gman", "arch:aarch64", "arch:arm64"];
+    let other = ["host:home-*", "user:root", "arch:x86_64"];
+
+    for val in matching {
+      assert!(
+        Target::from_str(val).unwrap().is_compatible(os, machine),
+        "{}",
+        val
+      );
+    }
+    for val in other {
+      assert!(
+        !Target::from_str(val).unwrap().is_compatible(os, machine),
+        "{}",
+        val
+      );
+    }
+  }
+
+  #[test]
+  fn should_pick_the_host_over_the_os() {
+    let targets = &[
+      Target::Macos,
+      Target::Cpu("aarch64".to_owned()),
+      Target::Host("work-*".to_owned()),
+      Target::Any,
+    ];
+
+    assert_eq!(
+      Target::pick(&client_os::Type::Macos.into(), &laptop(), &targets[..3]),
+      Some(Target::Host("work-*".to_owned()))
+    );
+    assert_eq!(
+      Target::fallbacks(&client_os::Type::Macos.into(), &laptop(), targets),
+      vec![
+        Target::Host("work-*".to_owned()),
+        Target::Cpu("aarch64".to_owned()),
+        Target::Macos,
+        Target::Any
+      ]
+    );
+  }
+
+  #[test]
+  fn should_give_machines_their_own_directories() {
+    assert_eq!(Target::Host("work-*".to_owned()).dir(), "host/work-*");
+    assert_eq!(Target::User("root".to_owned()).dir(), "user/root");
+    assert_eq!(Target::Cpu("aarch64".to_owned()).dir(), "arch/aarch64");
+    assert_eq!(Target::Cpu("aarch64".to_owned()).name(), "arch:aarch64");
+  }
+}
+
+#[cfg(test)]
+mod test_fallbacks {
+  use super::client_os;
+  use super::Target;
+  use dtflib::Machine;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_go_from_the_distro_to_any() {
+    let targets = &[Target::Any, Target::Linux, Target::Ubuntu];
+
+    assert_eq!(
+      Target::fallbacks(
+        &client_os::Type::Ubuntu.into(),
+        &Machine::default(),
+        targets
+      ),
+      vec![Target::Ubuntu, Target::Linux, Target::Any]
+    );
+  }
+
+  #[test]
+  fn should_leave_out_incompatible_targets() {
+    let targets = &[Target::Macos, Target::Arch, Target::Linux, Target::Macos];
+
+    assert_eq!(
+      Target::fallbacks(&client_os::Type::Arch.into(), &Machine::default(), targets),
+      vec![Target::Arch, Target::Linux]
+    );
+    assert_eq!(
+      Target::fallbacks(&client_os::Type::Macos.into(), &Machine::default(), targets),
+      vec![Target::Macos]
+    );
+  }
 }
 
 #[cfg(test)]
 mod test_pick {
   use super::client_os;
   use super::Target;
+  use dtflib::Machine;
   use pretty_assertions::assert_eq;
 
   #[test]
@@ -323,7 +833,10 @@ mod test_pick {
     let all_os = client_os::Type::all();
 
     for os in all_os {
-      assert_eq!(Target::pick(os, targets), None);
+      assert_eq!(
+        Target::pick(&(*os).into(), &Machine::default(), targets),
+        None
+      );
     }
   }
 
@@ -333,18 +846,23 @@ mod test_pick {
     let all_os = client_os::Type::all();
 
     for os in all_os {
-      assert_eq!(Target::pick(os, targets), Some(Target::Any));
+      assert_eq!(
+        Target::pick(&(*os).into(), &Machine::default(), targets),
+        Some(Target::Any)
+      );
     }
   }
 
   #[test]
-  fn all_available_targets_should_pick_any_if_there_is_one_amongst_others() {
+  fn all_available_targets_should_fall_back_to_any_amongst_others() {
     let targets = &[Target::Arch, Target::Any, Target::Ubuntu, Target::Macos];
-    let all_os = client_os::Type::all();
+    let pick = |os: client_os::Type| Target::pick(&os.into(), &Machine::default(), targets);
 
-    for os in all_os {
-      assert_eq!(Target::pick(os, targets), Some(Target::Any));
-    }
+    assert_eq!(pick(client_os::Type::Arch), Some(Target::Arch));
+    assert_eq!(pick(client_os::Type::Ubuntu), Some(Target::Ubuntu));
+    assert_eq!(pick(client_os::Type::Macos), Some(Target::Macos));
+    assert_eq!(pick(client_os::Type::Fedora), Some(Target::Any));
+    assert_eq!(pick(client_os::Type::Windows), Some(Target::Any));
   }
 
   #[test]
@@ -393,7 +911,7 @@ mod test_pick {
     ];
 
     for (expected_target, os) in pairs {
-      let target = Target::pick(os, targets);
+      let target = Target::pick(&(*os).into(), &Machine::default(), targets);
       assert_eq!(&target.unwrap(), expected_target);
     }
   }
@@ -421,7 +939,7 @@ mod test_pick {
     ];
 
     for distro in distros {
-      let target = Target::pick(distro, targets);
+      let target = Target::pick(&(*distro).into(), &Machine::default(), targets);
       assert_eq!(target.unwrap(), Target::Linux);
     }
   }
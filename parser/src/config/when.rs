@@ -0,0 +1,186 @@
+use crate::{Render, RenderState};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+/// Conditions a section or a block applies under, every one given has to hold
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct When {
+  /// A program found on `PATH`, or at the path given, which is taken the way `to` takes it
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub command: Option<String>,
+
+  /// An environment variable that is set to anything but an empty string
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub env: Option<String>,
+
+  /// A file that exists, `~` and relative paths are taken the way `to` takes them
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub file_exists: Option<String>,
+
+  /// Environment variables along with the values they are set to
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub env_equals: BTreeMap<String, String>,
+}
+
+impl When {
+  /// Tells the first condition that doesn't hold, `None` when they all do. Variables
+  /// come out of `var`, which is the environment of dtf outside of tests. A path that
+  /// refers to an undefined variable is an error
+  pub(crate) fn unmet<F>(&self, state: &RenderState, var: F) -> Result<Option<String>, String>
+  where
+    F: Fn(&str) -> Option<String>,
+  {
+    if let Some(command) = &self.command {
+      // a path, like `./bin/tool`, is relative to the config rather than to where dtf runs
+      let found = match command.contains('/') {
+        true => is_executable(&Render::from(command).render(state)?),
+        false => is_on_path(command, var("PATH")),
+      };
+
+      if !found {
+        return Ok(Some(format!("`{}` is not on PATH", command)));
+      }
+    }
+
+    if let Some(key) = &self.env {
+      if var(key).is_none_or(|val| val.is_empty()) {
+        return Ok(Some(format!("${} is not set", key)));
+      }
+    }
+
+    if let Some(path) = &self.file_exists {
+      let path = Render::from(path).render(state)?;
+
+      if fs::metadata(&path).is_err() {
+        return Ok(Some(format!("{} does not exist", path.display())));
+      }
+    }
+
+    for (key, expected) in &self.env_equals {
+      match var(key) {
+        Some(val) if &val == expected => {}
+        Some(val) => return Ok(Some(format!("${} is `{}`, not `{}`", key, val, expected))),
+        None => {
+          return Ok(Some(format!(
+            "${} is not set, expected `{}`",
+            key, expected
+          )))
+        }
+      }
+    }
+
+    Ok(None)
+  }
+}
+
+fn is_executable(file: &Path) -> bool {
+  fs::metadata(file).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+/// Whether `command` is an executable file in any of the directories of `path`
+fn is_on_path(command: &str, path: Option<String>) -> bool {
+  match path {
+    Some(path) => env::split_paths(&path).any(|dir| is_executable(&dir.join(command))),
+    None => false,
+  }
+}
+
+#[cfg(test)]
+mod test_when {
+  use super::When;
+  use crate::{RenderState, Vars};
+  use pretty_assertions::assert_eq;
+  use std::collections::HashMap;
+  use std::path::PathBuf;
+
+  fn unmet(when: &str, vars: &[(&str, &str)]) -> Option<String> {
+    let when: When = serde_json::from_str(when).unwrap();
+    let vars: HashMap<String, String> = vars
+      .iter()
+      .map(|(key, val)| (key.to_string(), val.to_string()))
+      .collect();
+
+    let base_dir = &std::env::current_dir().unwrap().join("examples");
+    let home_dir = &PathBuf::from("/home/gman");
+    let state = RenderState {
+      home_dir,
+      base_dir,
+      source_dir: "",
+      vars: &Vars::default(),
+    };
+
+    when.unmet(&state, |key| vars.get(key).cloned()).unwrap()
+  }
+
+  #[test]
+  fn should_hold_with_nothing_to_check() {
+    assert_eq!(unmet("{}", &[]), None);
+  }
+
+  #[test]
+  fn should_look_for_commands_on_path() {
+    assert_eq!(
+      unmet(r#"{"command": "sh"}"#, &[("PATH", "/usr/bin:/bin")]),
+      None
+    );
+    assert_eq!(
+      unmet(r#"{"command": "sh"}"#, &[("PATH", "/nowhere")]),
+      Some("`sh` is not on PATH".to_owned())
+    );
+    assert_eq!(unmet(r#"{"command": "/bin/sh"}"#, &[]), None);
+  }
+
+  #[test]
+  fn should_take_command_paths_relative_to_the_config() {
+    assert_eq!(unmet(r#"{"command": "./a27/bin/tool"}"#, &[]), None);
+    assert_eq!(
+      unmet(r#"{"command": "~/bin/tool"}"#, &[]),
+      Some("`~/bin/tool` is not on PATH".to_owned())
+    );
+  }
+
+  #[test]
+  fn should_check_the_environment() {
+    assert_eq!(
+      unmet(
+        r#"{"env": "WAYLAND_DISPLAY"}"#,
+        &[("WAYLAND_DISPLAY", "wayland-0")]
+      ),
+      None
+    );
+    assert_eq!(
+      unmet(r#"{"env": "WAYLAND_DISPLAY"}"#, &[("WAYLAND_DISPLAY", "")]),
+      Some("$WAYLAND_DISPLAY is not set".to_owned())
+    );
+
+    let when = r#"{"env_equals": {"XDG_CURRENT_DESKTOP": "GNOME"}}"#;
+    assert_eq!(unmet(when, &[("XDG_CURRENT_DESKTOP", "GNOME")]), None);
+    assert_eq!(
+      unmet(when, &[("XDG_CURRENT_DESKTOP", "KDE")]),
+      Some("$XDG_CURRENT_DESKTOP is `KDE`, not `GNOME`".to_owned())
+    );
+    assert_eq!(
+      unmet(when, &[]),
+      Some("$XDG_CURRENT_DESKTOP is not set, expected `GNOME`".to_owned())
+    );
+  }
+
+  #[test]
+  fn should_check_files_relative_to_the_config() {
+    assert_eq!(unmet(r#"{"file_exists": "a01"}"#, &[]), None);
+    assert_eq!(
+      unmet(r#"{"file_exists": "~/.no-such-file"}"#, &[]),
+      Some("/home/gman/.no-such-file does not exist".to_owned())
+    );
+  }
+
+  #[test]
+  fn should_refuse_unknown_conditions() {
+    assert!(serde_json::from_str::<When>(r#"{"os": "linux"}"#).is_err());
+  }
+}
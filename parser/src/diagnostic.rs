@@ -0,0 +1,28 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// How much a diagnostic matters
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Level {
+  /// Looks like a mistake in the config
+  Warning,
+  /// A block left out on purpose, its targets or its conditions don't hold here
+  Skipped,
+}
+
+/// Something off about a block that doesn't stop the config from being mapped
+#[derive(Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+  /// Name of the block as it is written in the config
+  pub name: String,
+  pub message: String,
+  pub level: Level,
+  /// Where the files of a block skipped on its conditions would go, they are still its own
+  pub dsts: Vec<PathBuf>,
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}: {}", self.name, self.message)
+  }
+}
@@ -0,0 +1,194 @@
+use crate::config::Block;
+use glob::Pattern;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+fn walk_into(
+  root: &Path,
+  rel: &Path,
+  exclude: &[Pattern],
+  files: &mut Vec<PathBuf>,
+  seen: &mut Vec<PathBuf>,
+) -> Result<()> {
+  // a symlink back to a directory the walk is already within would never end
+  let real = fs::canonicalize(root.join(rel))?;
+  if seen.contains(&real) {
+    return Err(Error::new(
+      ErrorKind::InvalidInput,
+      format!(
+        "`{}` links back to a directory it is in",
+        root.join(rel).display()
+      ),
+    ));
+  }
+  seen.push(real);
+
+  let mut entries: Vec<fs::DirEntry> = fs::read_dir(root.join(rel))?.collect::<Result<_>>()?;
+  entries.sort_by_key(|entry| entry.file_name());
+
+  for entry in entries {
+    let path = rel.join(entry.file_name());
+
+    if exclude.iter().any(|pattern| pattern.matches_path(&path)) {
+      continue;
+    }
+
+    // a symlink to a directory is walked like the directory, a dangling one is a file
+    let file_type = entry.file_type()?;
+    let is_dir = match file_type.is_symlink() {
+      true => fs::metadata(entry.path()).is_ok_and(|meta| meta.is_dir()),
+      false => file_type.is_dir(),
+    };
+
+    match is_dir {
+      true => walk_into(root, &path, exclude, files, seen)?,
+      false => files.push(path),
+    }
+  }
+
+  seen.pop();
+  Ok(())
+}
+
+/// Every file within the `dir`, relative to it and sorted, except for whatever matches
+/// one of the `exclude` patterns. Excluded directories are not looked into at all,
+/// symlinked ones are followed
+pub fn walk(dir: &Path, exclude: &[Pattern]) -> Result<Vec<PathBuf>> {
+  let mut files = vec![];
+  walk_into(dir, Path::new(""), exclude, &mut files, &mut vec![])?;
+
+  Ok(files)
+}
+
+fn exclude(block: &Block) -> Result<Vec<Pattern>> {
+  block
+    .exclude
+    .iter()
+    .map(|val| {
+      Pattern::new(val).map_err(|e| {
+        Error::new(
+          ErrorKind::InvalidInput,
+          format!("`{}` excludes `{}`, {}", block.name, val, e.msg),
+        )
+      })
+    })
+    .collect()
+}
+
+/// Whether the block name is a glob pattern rather than a name of a single file
+pub fn is_pattern(name: &str) -> bool {
+  name.contains(['*', '?', '['])
+}
+
+/// Names of the files matching the block pattern within the `src_dir`, relative to it
+/// and sorted. Directories never match, `bin/**` stands for every file under `bin`
+pub fn glob(src_dir: &Path, block: &Block) -> Result<Vec<String>> {
+  let exclude = exclude(block)?;
+
+  // a trailing `**` only ever matches directories
+  let name = match block.name.ends_with("**") {
+    true => format!("{}/*", block.name),
+    false => block.name.clone(),
+  };
+  let pattern = format!("{}/{}", Pattern::escape(&src_dir.to_string_lossy()), name);
+
+  let paths = glob::glob(&pattern).map_err(|e| {
+    Error::new(
+      ErrorKind::InvalidInput,
+      format!("`{}` is not a valid pattern, {}", block.name, e.msg),
+    )
+  })?;
+
+  let mut names = vec![];
+
+  for path in paths {
+    let path = path?;
+    let rel = path.strip_prefix(src_dir).unwrap();
+
+    if path.is_dir() || exclude.iter().any(|pattern| pattern.matches_path(rel)) {
+      continue;
+    }
+
+    names.push(rel.to_string_lossy().into_owned());
+  }
+
+  Ok(names)
+}
+
+/// Names of the files found within the directory of a recursive block, relative to the
+/// `src_dir` the block is mapped from. A directory that does not exist ends up being the
+/// only name, so that it is reported as a missing source like any other file
+pub fn recursive(src_dir: &Path, block: &Block) -> Result<Vec<String>> {
+  let dir = block.name.trim_end_matches('/');
+  let exclude = exclude(block)?;
+
+  match walk(&src_dir.join(dir), &exclude) {
+    Ok(files) => Ok(
+      files
+        .iter()
+        .map(|file| Path::new(dir).join(file).to_string_lossy().into_owned())
+        .collect(),
+    ),
+    Err(e) if e.kind() == ErrorKind::NotFound => Ok(vec![dir.to_owned()]),
+    Err(e) => Err(e),
+  }
+}
+
+#[cfg(test)]
+mod test_walk {
+  use super::walk;
+  use glob::Pattern;
+  use std::path::PathBuf;
+
+  fn dir() -> PathBuf {
+    std::env::current_dir()
+      .unwrap()
+      .join("examples/a20/files/linux/nvim")
+  }
+
+  #[test]
+  fn should_list_every_file_in_order() {
+    let actual = walk(&dir(), &[]).unwrap();
+
+    assert_eq!(
+      actual,
+      vec![
+        PathBuf::from("debug.log"),
+        PathBuf::from("init.lua"),
+        PathBuf::from("lua/options.lua"),
+        PathBuf::from("plugin/packer.lua"),
+      ]
+    );
+  }
+
+  #[test]
+  fn should_follow_symlinked_directories() {
+    let dir = std::env::current_dir().unwrap().join("examples/a29");
+    let actual = walk(&dir.join("files/nvim"), &[]).unwrap();
+
+    assert_eq!(
+      actual,
+      vec![PathBuf::from("init.lua"), PathBuf::from("lua/options.lua")]
+    );
+
+    let err = walk(&dir.join("loop"), &[]).unwrap_err();
+    assert!(err
+      .to_string()
+      .ends_with("links back to a directory it is in"));
+  }
+
+  #[test]
+  fn should_leave_out_excluded_files_and_directories() {
+    let exclude = vec![
+      Pattern::new("*.log").unwrap(),
+      Pattern::new("plugin").unwrap(),
+    ];
+    let actual = walk(&dir(), &exclude).unwrap();
+
+    assert_eq!(
+      actual,
+      vec![PathBuf::from("init.lua"), PathBuf::from("lua/options.lua")]
+    );
+  }
+}
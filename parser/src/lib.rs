@@ -2,18 +2,32 @@ use dtflib::{Context, DotFile};
 use read_file::read_file;
 use std::collections::HashMap;
 use std::io::Result;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 mod read_file;
 
+mod write_file;
+use write_file::write_file;
+
 mod config;
 use config::Config;
 
 mod mapping;
 
+mod expand;
+
+mod diagnostic;
+pub use diagnostic::{Diagnostic, Level};
+
 mod render;
 use render::{Render, RenderState};
 
+mod vars;
+use vars::Vars;
+
+mod adopt;
+pub use adopt::Adoption;
+
 pub struct Parser<'a> {
   cx: &'a Context<'a>,
   config: Option<Config>,
@@ -43,9 +57,32 @@ impl<'a> Parser<'a> {
     }
   }
 
-  pub fn parse(&mut self, path: &PathBuf) -> Result<HashMap<u32, DotFile>> {
+  /// Adds the file at `path` to the config read before, see `write_config` to persist it
+  pub fn adopt(
+    &mut self,
+    path: &Path,
+    target: Option<&str>,
+    section: Option<usize>,
+  ) -> Result<Adoption> {
+    let config = self.config.as_mut().expect("config has not been read");
+    adopt::adopt(self.cx, config, path, target, section)
+  }
+
+  /// Writes the config back in the same format it was read in
+  pub fn write_config(&self, path: &Path) -> Result<()> {
+    write_file(
+      path,
+      self.config.as_ref().expect("config has not been read"),
+    )
+  }
+
+  /// Maps the config, along with whatever looks off in it
+  pub fn parse(&mut self, path: &PathBuf) -> Result<(HashMap<u32, DotFile<'_>>, Vec<Diagnostic>)> {
     self.read_config(path)?;
 
-    Ok(mapping::map(self.cx, self.config.as_ref().unwrap())?)
+    let mut diagnostics = vec![];
+    let dotfiles = mapping::map(self.cx, self.config.as_ref().unwrap(), &mut diagnostics)?;
+
+    Ok((dotfiles, diagnostics))
   }
 }
@@ -1,50 +1,269 @@
 use crate::{
-  config::{Config, Target},
-  Render, RenderState,
+  config::{Block, Config, Expr, Section, Target},
+  expand, Diagnostic, Level, Render, RenderState, Vars,
 };
 use dtflib::{Context, DotFile};
-use std::collections::HashMap;
-use std::io::Result;
-
-pub fn map<'a>(cx: &Context, config: &'a Config) -> Result<HashMap<u32, DotFile<'a>>> {
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// Maps every block of the config to the files it stands for, patterns that match
+/// nothing end up in the `diagnostics`, so do the blocks skipped and why
+pub fn map<'a>(
+  cx: &Context,
+  config: &'a Config,
+  diagnostics: &mut Vec<Diagnostic>,
+) -> Result<HashMap<u32, DotFile<'a>>> {
   let mut id: u32 = 0;
   let mut ret: HashMap<u32, DotFile<'a>> = HashMap::new();
+  let vars = &Vars::new(cx, &config.vars).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
 
   for section in &config.map {
-    let target = Target::pick(cx.client_os, &section.target);
-
-    if target.is_none() {
-      continue;
-    }
-
-    let target = target.unwrap();
-
     for file in &section.files {
-      let to = Render::from(&file.to);
-      let from = Render::from(&section.from);
+      // a block may narrow or widen the targets of its section
+      let exprs = file.target.as_ref().unwrap_or(&section.target);
+      let targets = &Expr::select(exprs, cx.client_os, cx.machine);
+      let target = match Target::pick(cx.client_os, cx.machine, targets) {
+        Some(target) => target,
+        None => {
+          diagnostics.push(Diagnostic {
+            name: file.name.clone(),
+            message: "none of its targets suits this machine".to_owned(),
+            level: Level::Skipped,
+            dsts: vec![],
+          });
+          continue;
+        }
+      };
 
+      let source_dir = target.dir();
       let state = RenderState {
         home_dir: &cx.home_dir,
         base_dir: &cx.base_dir,
-        source_dir: target.dir(),
+        source_dir: &source_dir,
+        vars,
       };
 
-      id += 1;
+      // the conditions of the section go first, then the ones of the block
+      let mut unmet = None;
+      for when in [&section.when, &file.when]
+        .iter()
+        .filter_map(|when| when.as_ref())
+      {
+        unmet = when
+          .unmet(&state, |key| env::var(key).ok())
+          .map_err(|e| invalid(file, "when", e))?;
+
+        if unmet.is_some() {
+          break;
+        }
+      }
+
+      // the files of a block that only doesn't apply right now are still its own, so
+      // they are named even though they are left out, whatever it takes to name them
+      if let Some(reason) = unmet {
+        let dsts = map_block(section, file, cx, targets, &state, &mut vec![])
+          .map(|dotfiles| dotfiles.iter().map(DotFile::dst_file_path).collect())
+          .unwrap_or_default();
+
+        diagnostics.push(Diagnostic {
+          name: file.name.clone(),
+          message: reason,
+          level: Level::Skipped,
+          dsts,
+        });
+        continue;
+      }
+
+      for mut dotfile in map_block(section, file, cx, targets, &state, diagnostics)? {
+        id += 1;
+        dotfile.id = id;
+        ret.insert(id, dotfile);
+      }
+    }
+  }
 
-      let dotfile = DotFile {
-        id,
-        name: &file.name,
-        dst: to.render(&state),
-        src: from.render(&state),
-      };
+  Ok(ret)
+}
 
-      ret.insert(id, dotfile);
+/// Variables that can't be expanded are the block's fault, so it is named
+fn invalid(file: &Block, field: &str, e: String) -> Error {
+  Error::new(
+    ErrorKind::InvalidInput,
+    format!("`{}` of `{}`: {}", field, file.name, e),
+  )
+}
+
+/// The files a single block stands for, the ids are left for the caller to give
+fn map_block<'a>(
+  section: &'a Section,
+  file: &'a Block,
+  cx: &Context,
+  targets: &[Target],
+  state: &RenderState,
+  diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Vec<DotFile<'a>>> {
+  let to = Render::from(&file.to);
+  let from = Render::from(&section.from);
+
+  let src = from.render(state).map_err(|e| invalid(file, "from", e))?;
+  let dst = to.render(state).map_err(|e| invalid(file, "to", e))?;
+
+  // with the target in `from`, a file is looked for in the directories of every
+  // compatible target, the most specific one first
+  let has_target = section.from.contains("$TARGET") || section.from.contains("${TARGET}");
+  let mut candidates: Vec<PathBuf> = match has_target {
+    true => Target::fallbacks(cx.client_os, cx.machine, targets)
+      .iter()
+      .map(|one| {
+        from.render(&RenderState {
+          source_dir: &one.dir(),
+          ..*state
+        })
+      })
+      .collect::<std::result::Result<_, _>>()
+      .map_err(|e| invalid(file, "from", e))?,
+    false => vec![],
+  };
+
+  // `ubuntu` and `ubuntu>=22.04` share the same directory
+  let mut seen = HashSet::new();
+  candidates.retain(|dir| seen.insert(dir.clone()));
+
+  let candidates = match candidates.len() {
+    0 | 1 => vec![],
+    _ => candidates,
+  };
+
+  let owned =
+    |names: Vec<String>| -> Vec<Cow<'a, str>> { names.into_iter().map(Cow::Owned).collect() };
+
+  // a recursive block mirrors its tree, so the subdirectories have to be there
+  let (names, expanded, mkdir) = match file.recursive {
+    Some(true) => {
+      let dirs = source_dirs(&src, &candidates, |dir| dir.join(&file.name).exists());
+      (
+        owned(expand_in(&dirs, |dir| expand::recursive(dir, file))?),
+        true,
+        Some(section.mkdir.unwrap_or(true)),
+      )
+    }
+    _ if expand::is_pattern(&file.name) => {
+      let dirs = source_dirs(&src, &candidates, |_| true);
+      (
+        owned(expand_in(&dirs, |dir| expand::glob(dir, file))?),
+        true,
+        section.mkdir,
+      )
     }
+    _ => (
+      vec![Cow::Borrowed(file.name.as_str())],
+      false,
+      section.mkdir,
+    ),
+  };
+
+  if file.dst_name.is_some() && file.recursive != Some(true) && expanded {
+    return Err(Error::new(
+      ErrorKind::InvalidInput,
+      format!(
+        "`{}` is a pattern, it can't be linked `as` a single file",
+        file.name
+      ),
+    ));
+  }
+
+  let pattern: Option<Cow<'a, str>> = match expanded {
+    true => Some(Cow::Borrowed(&file.name)),
+    false => None,
+  };
+
+  if names.is_empty() {
+    diagnostics.push(Diagnostic {
+      name: file.name.clone(),
+      message: format!("matches no files in {}", src.display()),
+      level: Level::Warning,
+      dsts: vec![],
+    });
+  }
+
+  let mut ret = Vec::with_capacity(names.len());
+
+  for name in names {
+    let dst_name: Option<Cow<'a, str>> = match (&file.dst_name, expanded) {
+      (None, _) => None,
+      (Some(val), false) => Some(Cow::Borrowed(val)),
+      // it is the directory of a recursive block that gets another name
+      (Some(val), true) => {
+        let dir = file.name.trim_end_matches('/');
+        let rest = name.strip_prefix(dir).unwrap_or(&name);
+        Some(Cow::Owned(format!("{}{}", val.trim_end_matches('/'), rest)))
+      }
+    };
+
+    // the first directory that has the file wins, the picked target's one otherwise
+    let src = candidates
+      .iter()
+      .find(|dir| fs::symlink_metadata(dir.join(name.as_ref())).is_ok())
+      .unwrap_or(&src);
+
+    ret.push(DotFile {
+      id: 0,
+      name,
+      dst_name,
+      dst: dst.clone(),
+      src: src.clone(),
+      link: file.link,
+      relative: file.relative,
+      mkdir,
+      dir_mode: section.dir_mode,
+      pattern: pattern.clone(),
+      candidates: candidates.clone(),
+    });
   }
 
   Ok(ret)
 }
 
+/// Directories a pattern is expanded in, the candidates that `keep` agrees on or the only
+/// source directory when there are none
+fn source_dirs<F>(src: &Path, candidates: &[PathBuf], keep: F) -> Vec<PathBuf>
+where
+  F: Fn(&Path) -> bool,
+{
+  let dirs: Vec<PathBuf> = candidates.iter().filter(|dir| keep(dir)).cloned().collect();
+
+  match dirs.is_empty() {
+    true => vec![src.to_path_buf()],
+    false => dirs,
+  }
+}
+
+/// Expands a pattern in each of the `dirs`, a file found in many of them is listed once
+fn expand_in<F>(dirs: &[PathBuf], expand: F) -> Result<Vec<String>>
+where
+  F: Fn(&Path) -> Result<Vec<String>>,
+{
+  let mut names: Vec<String> = vec![];
+
+  for dir in dirs {
+    for name in expand(dir)? {
+      if !names.contains(&name) {
+        names.push(name);
+      }
+    }
+  }
+
+  if dirs.len() > 1 {
+    names.sort();
+  }
+
+  Ok(names)
+}
+
 #[cfg(test)]
 mod tests {
   use super::map;
@@ -88,18 +307,26 @@ mod tests {
     let cx = Context {
       base_dir,
       home_dir: &home_dir,
-      client_os: &client_os::Type::Linux,
+      client_os: &client_os::Type::Linux.into(),
+      machine: &Default::default(),
       config_path,
       child: true,
     };
 
-    let actual = map(&cx, &config)?;
+    let actual = map(&cx, &config, &mut vec![])?;
 
     let expected = DotFile {
       id: 1,
-      name: "file.sh",
+      name: "file.sh".into(),
+      dst_name: None,
       src: PathBuf::from(&base_dir.join("files/linux")),
       dst: PathBuf::from(&home_dir),
+      link: None,
+      relative: None,
+      mkdir: None,
+      dir_mode: None,
+      pattern: None,
+      candidates: vec![],
     };
 
     assert_eq!(
@@ -122,12 +349,13 @@ mod tests {
     let cx = Context {
       base_dir,
       home_dir: &home_dir,
-      client_os: &client_os::Type::Macos,
+      client_os: &client_os::Type::Macos.into(),
+      machine: &Default::default(),
       config_path,
       child: true,
     };
 
-    let actual = map(&cx, &config)?;
+    let actual = map(&cx, &config, &mut vec![])?;
     let expected = HashMap::new();
 
     assert_eq!(
@@ -149,12 +377,13 @@ mod tests {
     let cx = Context {
       base_dir,
       home_dir: &home_dir,
-      client_os: &client_os::Type::Macos,
+      client_os: &client_os::Type::Macos.into(),
+      machine: &Default::default(),
       config_path,
       child: true,
     };
 
-    let actual = map(&cx, &config)?;
+    let actual = map(&cx, &config, &mut vec![])?;
 
     let expected = HashMap::new();
 
@@ -177,18 +406,26 @@ mod tests {
     let cx = Context {
       base_dir,
       home_dir: &home_dir,
-      client_os: &client_os::Type::Macos,
+      client_os: &client_os::Type::Macos.into(),
+      machine: &Default::default(),
       config_path,
       child: true,
     };
 
-    let actual = map(&cx, &config)?;
+    let actual = map(&cx, &config, &mut vec![])?;
 
     let expected = DotFile {
       id: 1,
-      name: "file.sh",
+      name: "file.sh".into(),
+      dst_name: None,
       src: PathBuf::from(&base_dir.join("files/macos")),
       dst: PathBuf::from(&home_dir),
+      link: None,
+      relative: None,
+      mkdir: None,
+      dir_mode: None,
+      pattern: None,
+      candidates: vec![],
     };
 
     assert_eq!(
@@ -211,18 +448,26 @@ mod tests {
     let cx = Context {
       base_dir,
       home_dir: &home_dir,
-      client_os: &client_os::Type::Macos,
+      client_os: &client_os::Type::Macos.into(),
+      machine: &Default::default(),
       config_path,
       child: true,
     };
 
-    let actual = map(&cx, &config)?;
+    let actual = map(&cx, &config, &mut vec![])?;
 
     let expected = DotFile {
       id: 1,
-      name: "file.sh",
+      name: "file.sh".into(),
+      dst_name: None,
       src: PathBuf::from(&base_dir.join("files")),
       dst: PathBuf::from(&home_dir),
+      link: None,
+      relative: None,
+      mkdir: None,
+      dir_mode: None,
+      pattern: None,
+      candidates: vec![],
     };
 
     assert_eq!(
@@ -245,18 +490,26 @@ mod tests {
     let cx = Context {
       base_dir,
       home_dir: &home_dir,
-      client_os: &client_os::Type::Macos,
+      client_os: &client_os::Type::Macos.into(),
+      machine: &Default::default(),
       config_path,
       child: true,
     };
 
-    let actual = map(&cx, &config)?;
+    let actual = map(&cx, &config, &mut vec![])?;
 
     let expected = DotFile {
       id: 1,
-      name: "file.sh",
-      src: PathBuf::from(&base_dir.join("files")),
+      name: "file.sh".into(),
+      dst_name: None,
+      src: PathBuf::from(&base_dir.join("files/macos")),
       dst: PathBuf::from(&home_dir),
+      link: None,
+      relative: None,
+      mkdir: None,
+      dir_mode: None,
+      pattern: None,
+      candidates: vec![base_dir.join("files/macos"), base_dir.join("files")],
     };
 
     println!("\n|> {:}\n", &config_path.to_str().unwrap());
@@ -264,7 +517,7 @@ mod tests {
     assert_eq!(
       actual,
       to_map(expected),
-      "when `target` contains `any` in it alongside with other targets, treat it like `any` anyway"
+      "when `target` contains `any` in it alongside with other targets, `any` is the fallback"
     );
 
     Ok(())
@@ -281,18 +534,26 @@ mod tests {
     let cx = Context {
       base_dir,
       home_dir: &home_dir,
-      client_os: &client_os::Type::Macos,
+      client_os: &client_os::Type::Macos.into(),
+      machine: &Default::default(),
       config_path,
       child: true,
     };
 
-    let actual = map(&cx, &config)?;
+    let actual = map(&cx, &config, &mut vec![])?;
 
     let expected = DotFile {
       id: 1,
-      name: "file.sh",
+      name: "file.sh".into(),
+      dst_name: None,
       src: PathBuf::from(&base_dir.join("files")),
       dst: PathBuf::from(&home_dir),
+      link: None,
+      relative: None,
+      mkdir: None,
+      dir_mode: None,
+      pattern: None,
+      candidates: vec![],
     };
 
     println!("\n|> {:}\n", &config_path.to_str().unwrap());
@@ -317,18 +578,26 @@ mod tests {
     let cx = Context {
       base_dir,
       home_dir: &home_dir,
-      client_os: &client_os::Type::Macos,
+      client_os: &client_os::Type::Macos.into(),
+      machine: &Default::default(),
       config_path,
       child: true,
     };
 
-    let actual = map(&cx, &config)?;
+    let actual = map(&cx, &config, &mut vec![])?;
 
     let expected = DotFile {
       id: 1,
-      name: "file.sh",
+      name: "file.sh".into(),
+      dst_name: None,
       src: PathBuf::from(&base_dir.join("files/macos")),
       dst: PathBuf::from(&home_dir),
+      link: None,
+      relative: None,
+      mkdir: None,
+      dir_mode: None,
+      pattern: None,
+      candidates: vec![],
     };
 
     println!("\n|> {:}\n", &config_path.to_str().unwrap());
@@ -353,18 +622,26 @@ mod tests {
     let cx = Context {
       base_dir,
       home_dir: &home_dir,
-      client_os: &client_os::Type::Linux,
+      client_os: &client_os::Type::Linux.into(),
+      machine: &Default::default(),
       config_path,
       child: true,
     };
 
-    let actual = map(&cx, &config)?;
+    let actual = map(&cx, &config, &mut vec![])?;
 
     let expected = DotFile {
       id: 1,
-      name: "ide-script.sh",
+      name: "ide-script.sh".into(),
+      dst_name: None,
       src: PathBuf::from(&base_dir.join("files/linux")),
       dst: PathBuf::from(&home_dir).join("Code"),
+      link: None,
+      relative: None,
+      mkdir: None,
+      dir_mode: None,
+      pattern: None,
+      candidates: vec![],
     };
 
     println!("\n|> {:}\n", &config_path.to_str().unwrap());
@@ -389,18 +666,26 @@ mod tests {
     let cx = Context {
       base_dir,
       home_dir: &home_dir,
-      client_os: &client_os::Type::Linux,
+      client_os: &client_os::Type::Linux.into(),
+      machine: &Default::default(),
       config_path,
       child: true,
     };
 
-    let actual = map(&cx, &config)?;
+    let actual = map(&cx, &config, &mut vec![])?;
 
     let expected = DotFile {
       id: 1,
-      name: "file.sh",
+      name: "file.sh".into(),
+      dst_name: None,
       src: PathBuf::from(&base_dir.join("files")),
       dst: PathBuf::from("/etc/some"),
+      link: None,
+      relative: None,
+      mkdir: None,
+      dir_mode: None,
+      pattern: None,
+      candidates: vec![],
     };
 
     println!("\n|> {:}\n", &config_path.to_str().unwrap());
@@ -429,18 +714,26 @@ mod tests {
       let cx = Context {
         base_dir,
         home_dir: &home_dir,
-        client_os: &client_os::Type::Linux,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
         config_path,
         child: true,
       };
 
-      let actual = map(&cx, &config)?;
+      let actual = map(&cx, &config, &mut vec![])?;
 
       let expected = DotFile {
         id: 1,
-        name: "file.sh",
+        name: "file.sh".into(),
+        dst_name: None,
         src: PathBuf::from(&base_dir.join("otherstuff")),
         dst: PathBuf::from(&home_dir).join("some"),
+        link: None,
+        relative: None,
+        mkdir: None,
+        dir_mode: None,
+        pattern: None,
+        candidates: vec![],
       };
 
       println!("\n|> {:}\n", &config_path.to_str().unwrap());
@@ -464,18 +757,26 @@ mod tests {
       let cx = Context {
         base_dir,
         home_dir: &home_dir,
-        client_os: &client_os::Type::Linux,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
         config_path,
         child: true,
       };
 
-      let actual = map(&cx, &config)?;
+      let actual = map(&cx, &config, &mut vec![])?;
 
       let expected = DotFile {
         id: 1,
-        name: "file.sh",
+        name: "file.sh".into(),
+        dst_name: None,
         src: PathBuf::from(&base_dir).join("otherstuff"),
         dst: PathBuf::from(&home_dir).join("some"),
+        link: None,
+        relative: None,
+        mkdir: None,
+        dir_mode: None,
+        pattern: None,
+        candidates: vec![],
       };
 
       println!("\n|> {:}\n", &config_path.to_str().unwrap());
@@ -500,18 +801,26 @@ mod tests {
       let cx = Context {
         base_dir,
         home_dir: &home_dir,
-        client_os: &client_os::Type::Linux,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
         config_path,
         child: true,
       };
 
-      let actual = map(&cx, &config)?;
+      let actual = map(&cx, &config, &mut vec![])?;
 
       let expected = DotFile {
         id: 1,
-        name: "file.sh",
+        name: "file.sh".into(),
+        dst_name: None,
         src: PathBuf::from(&home_dir).join("backup"),
         dst: PathBuf::from(&home_dir).join("some"),
+        link: None,
+        relative: None,
+        mkdir: None,
+        dir_mode: None,
+        pattern: None,
+        candidates: vec![],
       };
 
       println!("\n|> {:}\n", &config_path.to_str().unwrap());
@@ -536,18 +845,26 @@ mod tests {
       let cx = Context {
         base_dir,
         home_dir: &home_dir,
-        client_os: &client_os::Type::Linux,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
         config_path,
         child: true,
       };
 
-      let actual = map(&cx, &config)?;
+      let actual = map(&cx, &config, &mut vec![])?;
 
       let expected = DotFile {
         id: 1,
-        name: "file.sh",
+        name: "file.sh".into(),
+        dst_name: None,
         src: PathBuf::from("/my/bucket/with/stuff/by/linux"),
         dst: PathBuf::from(&home_dir).join("some"),
+        link: None,
+        relative: None,
+        mkdir: None,
+        dir_mode: None,
+        pattern: None,
+        candidates: vec![],
       };
 
       println!("\n|> {:}\n", &config_path.to_str().unwrap());
@@ -572,18 +889,26 @@ mod tests {
       let cx = Context {
         base_dir,
         home_dir: &home_dir,
-        client_os: &client_os::Type::Linux,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
         config_path,
         child: true,
       };
 
-      let actual = map(&cx, &config)?;
+      let actual = map(&cx, &config, &mut vec![])?;
 
       let expected = DotFile {
         id: 1,
-        name: "file.sh",
+        name: "file.sh".into(),
+        dst_name: None,
         src: PathBuf::from("/my/bucket/with/stuff/by"),
         dst: PathBuf::from(&home_dir).join("some"),
+        link: None,
+        relative: None,
+        mkdir: None,
+        dir_mode: None,
+        pattern: None,
+        candidates: vec![],
       };
 
       println!("\n|> {:}\n", &config_path.to_str().unwrap());
@@ -607,18 +932,26 @@ mod tests {
       let cx = Context {
         base_dir,
         home_dir: &home_dir,
-        client_os: &client_os::Type::Linux,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
         config_path,
         child: true,
       };
 
-      let actual = map(&cx, &config)?;
+      let actual = map(&cx, &config, &mut vec![])?;
 
       let expected = DotFile {
         id: 1,
-        name: "file.sh",
+        name: "file.sh".into(),
+        dst_name: None,
         src: PathBuf::from(&base_dir).join("stuff"),
         dst: PathBuf::from(&home_dir).join("some"),
+        link: None,
+        relative: None,
+        mkdir: None,
+        dir_mode: None,
+        pattern: None,
+        candidates: vec![],
       };
 
       println!("\n|> {:}\n", &config_path.to_str().unwrap());
@@ -632,4 +965,681 @@ mod tests {
       Ok(())
     }
   }
+
+  mod block_based_link {
+    use super::*;
+    use dtflib::LinkMode;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn a18() -> io::Result<()> {
+      let base_dir = &base_dir("a18");
+      let home_dir = &FakeHomeDir::linux();
+      let config_path = &base_dir.join("dotthefiles.yml");
+
+      let config = read_file(config_path)?;
+
+      let cx = Context {
+        base_dir,
+        home_dir,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
+        config_path,
+        child: true,
+      };
+
+      let actual = map(&cx, &config, &mut vec![])?;
+
+      let mut expected = HashMap::new();
+      expected.insert(
+        1,
+        DotFile {
+          id: 1,
+          name: "file.sh".into(),
+          dst_name: None,
+          src: PathBuf::from(&base_dir.join("files/linux")),
+          dst: PathBuf::from(&home_dir),
+          link: None,
+          relative: None,
+          mkdir: None,
+          dir_mode: None,
+          pattern: None,
+          candidates: vec![],
+        },
+      );
+      expected.insert(
+        2,
+        DotFile {
+          id: 2,
+          name: "other.sh".into(),
+          dst_name: None,
+          src: PathBuf::from(&base_dir.join("files/linux")),
+          dst: PathBuf::from(&home_dir),
+          link: Some(LinkMode::Symlink),
+          relative: Some(true),
+          mkdir: None,
+          dir_mode: None,
+          pattern: None,
+          candidates: vec![],
+        },
+      );
+
+      assert_eq!(
+        actual, expected,
+        "should carry the per-file link mode and leave the rest to the run-wide one"
+      );
+
+      Ok(())
+    }
+  }
+
+  mod section_based_mkdir {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn a19() -> io::Result<()> {
+      let base_dir = &base_dir("a19");
+      let home_dir = &FakeHomeDir::linux();
+      let config_path = &base_dir.join("dotthefiles.yml");
+
+      let config = read_file(config_path)?;
+
+      let cx = Context {
+        base_dir,
+        home_dir,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
+        config_path,
+        child: true,
+      };
+
+      let actual = map(&cx, &config, &mut vec![])?;
+
+      let mut expected = HashMap::new();
+      expected.insert(
+        1,
+        DotFile {
+          id: 1,
+          name: "alacritty.yml".into(),
+          dst_name: None,
+          src: PathBuf::from(&base_dir.join("files/linux")),
+          dst: home_dir.join(".config/alacritty"),
+          link: None,
+          relative: None,
+          mkdir: Some(true),
+          dir_mode: Some(0o700),
+          pattern: None,
+          candidates: vec![],
+        },
+      );
+      expected.insert(
+        2,
+        DotFile {
+          id: 2,
+          name: "file.sh".into(),
+          dst_name: None,
+          src: PathBuf::from(&base_dir.join("files/linux")),
+          dst: PathBuf::from(&home_dir),
+          link: None,
+          relative: None,
+          mkdir: None,
+          dir_mode: None,
+          pattern: None,
+          candidates: vec![],
+        },
+      );
+
+      assert_eq!(
+        actual, expected,
+        "should carry the section directory options over to each of its files"
+      );
+
+      Ok(())
+    }
+  }
+
+  mod recursive_block {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn a20() -> io::Result<()> {
+      let base_dir = &base_dir("a20");
+      let home_dir = &FakeHomeDir::linux();
+      let config_path = &base_dir.join("dotthefiles.yml");
+
+      let config = read_file(config_path)?;
+
+      let cx = Context {
+        base_dir,
+        home_dir,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
+        config_path,
+        child: true,
+      };
+
+      let actual = map(&cx, &config, &mut vec![])?;
+
+      let mut expected = HashMap::new();
+      for (id, name) in [(1, "nvim/init.lua"), (2, "nvim/lua/options.lua")] {
+        expected.insert(
+          id,
+          DotFile {
+            id,
+            name: name.into(),
+            dst_name: None,
+            src: PathBuf::from(&base_dir.join("files/linux")),
+            dst: home_dir.join(".config"),
+            link: None,
+            relative: None,
+            mkdir: Some(true),
+            dir_mode: None,
+            pattern: Some("nvim/".into()),
+            candidates: vec![],
+          },
+        );
+      }
+      expected.insert(
+        3,
+        DotFile {
+          id: 3,
+          name: "file.sh".into(),
+          dst_name: None,
+          src: PathBuf::from(&base_dir.join("files/linux")),
+          dst: PathBuf::from(&home_dir),
+          link: None,
+          relative: None,
+          mkdir: None,
+          dir_mode: None,
+          pattern: None,
+          candidates: vec![],
+        },
+      );
+
+      assert_eq!(
+        actual, expected,
+        "should expand a recursive block into every file of its tree but the excluded ones"
+      );
+
+      Ok(())
+    }
+  }
+
+  mod glob_block {
+    use super::*;
+    use crate::{Diagnostic, Level};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn a21() -> io::Result<()> {
+      let base_dir = &base_dir("a21");
+      let home_dir = &FakeHomeDir::linux();
+      let config_path = &base_dir.join("dotthefiles.yml");
+
+      let config = read_file(config_path)?;
+
+      let cx = Context {
+        base_dir,
+        home_dir,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
+        config_path,
+        child: true,
+      };
+
+      let mut diagnostics = vec![];
+      let actual = map(&cx, &config, &mut diagnostics)?;
+
+      let mut expected = HashMap::new();
+      let files = vec![
+        (1, "aliases.zsh", "*.zsh"),
+        (2, "prompt.zsh", "*.zsh"),
+        (3, "bin/backup", "bin/**"),
+        (4, "bin/tools/sync", "bin/**"),
+      ];
+      for (id, name, pattern) in files {
+        expected.insert(
+          id,
+          DotFile {
+            id,
+            name: name.into(),
+            dst_name: None,
+            src: PathBuf::from(&base_dir.join("files/linux")),
+            dst: PathBuf::from(&home_dir),
+            link: None,
+            relative: None,
+            mkdir: None,
+            dir_mode: None,
+            pattern: Some(pattern.into()),
+            candidates: vec![],
+          },
+        );
+      }
+
+      assert_eq!(
+        actual, expected,
+        "should expand each pattern into the files it matches"
+      );
+
+      assert_eq!(
+        diagnostics,
+        vec![Diagnostic {
+          name: "*.fish".to_owned(),
+          message: format!(
+            "matches no files in {}",
+            base_dir.join("files/linux").display()
+          ),
+          level: Level::Warning,
+          dsts: vec![],
+        }],
+        "should tell about the pattern that matches nothing"
+      );
+
+      Ok(())
+    }
+  }
+
+  mod dst_name {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn a22() -> io::Result<()> {
+      let base_dir = &base_dir("a22");
+      let home_dir = &FakeHomeDir::linux();
+      let config_path = &base_dir.join("dotthefiles.yml");
+
+      let config = read_file(config_path)?;
+
+      let cx = Context {
+        base_dir,
+        home_dir,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
+        config_path,
+        child: true,
+      };
+
+      let actual = map(&cx, &config, &mut vec![])?;
+
+      let mut expected = HashMap::new();
+      let files = vec![
+        (1, "zshrc", ".zshrc"),
+        (2, "gitconfig", ".gitconfig"),
+        (3, "gitconfig", ".gitconfig-work"),
+      ];
+      for (id, name, dst_name) in files {
+        expected.insert(
+          id,
+          DotFile {
+            id,
+            name: name.into(),
+            dst_name: Some(dst_name.into()),
+            src: PathBuf::from(&base_dir.join("files/linux")),
+            dst: PathBuf::from(&home_dir),
+            link: None,
+            relative: None,
+            mkdir: None,
+            dir_mode: None,
+            pattern: None,
+            candidates: vec![],
+          },
+        );
+      }
+
+      assert_eq!(
+        actual, expected,
+        "should let a single source feed destinations of other names"
+      );
+
+      Ok(())
+    }
+  }
+
+  mod block_based_target {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn a23() -> io::Result<()> {
+      let base_dir = &base_dir("a23");
+      let home_dir = &FakeHomeDir::linux();
+      let config_path = &base_dir.join("dotthefiles.yml");
+
+      let config = read_file(config_path)?;
+
+      let cx = Context {
+        base_dir,
+        home_dir,
+        client_os: &client_os::Type::Ubuntu.into(),
+        machine: &Default::default(),
+        config_path,
+        child: true,
+      };
+
+      let actual = map(&cx, &config, &mut vec![])?;
+
+      let expected = DotFile {
+        id: 1,
+        name: "other.sh".into(),
+        dst_name: None,
+        src: PathBuf::from(&base_dir.join("files/linux")),
+        dst: PathBuf::from(&home_dir),
+        link: None,
+        relative: None,
+        mkdir: None,
+        dir_mode: None,
+        pattern: None,
+        candidates: vec![],
+      };
+
+      assert_eq!(
+        actual,
+        to_map(expected),
+        "should pick the targets of the block over the ones of its section"
+      );
+
+      Ok(())
+    }
+  }
+
+  mod source_fallback {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn a24() -> io::Result<()> {
+      let base_dir = &base_dir("a24");
+      let home_dir = &FakeHomeDir::linux();
+      let config_path = &base_dir.join("dotthefiles.yml");
+
+      let config = read_file(config_path)?;
+
+      let cx = Context {
+        base_dir,
+        home_dir,
+        client_os: &client_os::Type::Ubuntu.into(),
+        machine: &Default::default(),
+        config_path,
+        child: true,
+      };
+
+      let actual = map(&cx, &config, &mut vec![])?;
+
+      let candidates = vec![
+        base_dir.join("files/ubuntu"),
+        base_dir.join("files/linux"),
+        base_dir.join("files"),
+      ];
+      let dotfile = |id: u32, name: &'static str, src: &str| DotFile {
+        id,
+        name: name.into(),
+        dst_name: None,
+        src: base_dir.join(src),
+        dst: PathBuf::from(&home_dir),
+        link: None,
+        relative: None,
+        mkdir: None,
+        dir_mode: None,
+        pattern: None,
+        candidates: candidates.clone(),
+      };
+
+      let mut expected = HashMap::new();
+      expected.insert(1, dotfile(1, "aliases.sh", "files/ubuntu"));
+      expected.insert(2, dotfile(2, "env.sh", "files/linux"));
+      expected.insert(3, dotfile(3, "profile.sh", "files"));
+      expected.insert(4, dotfile(4, "missing.sh", "files/ubuntu"));
+
+      assert_eq!(
+        actual, expected,
+        "should take each file from the most specific directory that has it"
+      );
+
+      Ok(())
+    }
+  }
+
+  mod target_expressions {
+    use super::*;
+    use dtflib::Machine;
+    use pretty_assertions::assert_eq;
+
+    fn names(actual: &HashMap<u32, DotFile>) -> Vec<String> {
+      let mut names: Vec<String> = actual.values().map(|one| one.name.to_string()).collect();
+      names.sort();
+      names
+    }
+
+    #[test]
+    fn a25() -> io::Result<()> {
+      let base_dir = &base_dir("a25");
+      let home_dir = &FakeHomeDir::linux();
+      let config_path = &base_dir.join("dotthefiles.yml");
+
+      let config = read_file(config_path)?;
+
+      let machine = |arch: &str| Machine {
+        arch: arch.to_owned(),
+        ..Machine::default()
+      };
+
+      let cases = [
+        (client_os::Type::Ubuntu, "x86_64", vec!["apt.sh"]),
+        (client_os::Type::Arch, "x86_64", vec![]),
+        (client_os::Type::Macos, "aarch64", vec!["brew.sh"]),
+        (client_os::Type::Macos, "x86_64", vec![]),
+      ];
+
+      for (os, arch, expected) in cases {
+        let cx = Context {
+          base_dir,
+          home_dir,
+          client_os: &os.into(),
+          machine: &machine(arch),
+          config_path,
+          child: true,
+        };
+
+        let actual = map(&cx, &config, &mut vec![])?;
+
+        assert_eq!(names(&actual), expected, "on {:?} {}", os, arch);
+      }
+
+      Ok(())
+    }
+  }
+
+  mod custom_targets {
+    use super::*;
+    use dtflib::Machine;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn a26() -> io::Result<()> {
+      let base_dir = &base_dir("a26");
+      let home_dir = &FakeHomeDir::linux();
+      let config_path = &base_dir.join("dotthefiles.yml");
+
+      let config = read_file(config_path)?;
+
+      let machine = |host: &str| Machine {
+        host: host.to_owned(),
+        ..Machine::default()
+      };
+
+      let cases = [
+        (client_os::Type::Macos, "laptop", Some("files/workstation")),
+        (client_os::Type::Fedora, "laptop", Some("files/workstation")),
+        (client_os::Type::Debian, "srv-1", Some("files/srv")),
+        (client_os::Type::Debian, "laptop", None),
+      ];
+
+      for (os, host, expected) in cases {
+        let cx = Context {
+          base_dir,
+          home_dir,
+          client_os: &os.into(),
+          machine: &machine(host),
+          config_path,
+          child: true,
+        };
+
+        let actual = map(&cx, &config, &mut vec![])?;
+        let src = actual.values().next().map(|one| one.src.clone());
+
+        assert_eq!(
+          src,
+          expected.map(|dir| base_dir.join(dir)),
+          "on {:?} {}",
+          os,
+          host
+        );
+      }
+
+      Ok(())
+    }
+  }
+
+  mod conditions {
+    use super::*;
+    use crate::{Diagnostic, Level};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn a27() -> io::Result<()> {
+      let base_dir = &base_dir("a27");
+      let home_dir = &FakeHomeDir::linux();
+      let config_path = &base_dir.join("dotthefiles.yml");
+
+      let config = read_file(config_path)?;
+
+      let cx = Context {
+        base_dir,
+        home_dir,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
+        config_path,
+        child: true,
+      };
+
+      let mut diagnostics = vec![];
+      let actual = map(&cx, &config, &mut diagnostics)?;
+
+      let names: Vec<&str> = actual.values().map(|one| one.name.as_ref()).collect();
+      assert_eq!(names, vec!["kitty.conf"]);
+
+      let skipped = |name: &str, message: &str, dsts: Vec<PathBuf>| Diagnostic {
+        name: name.to_owned(),
+        message: message.to_owned(),
+        level: Level::Skipped,
+        dsts,
+      };
+
+      assert_eq!(
+        diagnostics,
+        vec![
+          skipped(
+            "extra.conf",
+            "/home/gman/.extra does not exist",
+            vec![home_dir.join("extra.conf")]
+          ),
+          skipped(
+            "other.sh",
+            "`/no/such/command` is not on PATH",
+            vec![home_dir.join("other.sh")]
+          ),
+          skipped("brew.sh", "none of its targets suits this machine", vec![]),
+        ],
+        "should still name where the files of a block left out on its conditions go"
+      );
+
+      Ok(())
+    }
+  }
+
+  mod variables {
+    use super::*;
+    use crate::config::Config;
+    use dtflib::Machine;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn a28() -> io::Result<()> {
+      let base_dir = &base_dir("a28");
+      let home_dir = &FakeHomeDir::linux();
+      let config_path = &base_dir.join("dotthefiles.yml");
+
+      let config = read_file(config_path)?;
+
+      let cx = Context {
+        base_dir,
+        home_dir,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Machine {
+          host: "work-1".to_owned(),
+          ..Machine::default()
+        },
+        config_path,
+        child: true,
+      };
+
+      let actual = map(&cx, &config, &mut vec![])?;
+
+      let mut actual: Vec<(String, PathBuf, PathBuf)> = actual
+        .values()
+        .map(|one| (one.name.to_string(), one.src.clone(), one.dst.clone()))
+        .collect();
+      actual.sort();
+
+      let src = base_dir.join("files/linux");
+      assert_eq!(
+        actual,
+        vec![
+          (
+            "fallback.conf".to_owned(),
+            src.clone(),
+            home_dir.join("fallback")
+          ),
+          (
+            "host.conf".to_owned(),
+            src.clone(),
+            home_dir.join(".config/work-1")
+          ),
+          ("tool.conf".to_owned(), src, home_dir.join("apps/tool")),
+        ]
+      );
+
+      Ok(())
+    }
+
+    #[test]
+    fn should_name_the_block_of_an_undefined_variable() {
+      let base_dir = &base_dir("a28");
+      let home_dir = &FakeHomeDir::linux();
+      let config_path = &base_dir.join("dotthefiles.yml");
+
+      let config: Config = serde_json::from_str(
+        r#"{"map": [{"files": [{"name": "init.lua", "to": "${DTF_NO_SUCH_VAR}/nvim"}]}]}"#,
+      )
+      .unwrap();
+
+      let cx = Context {
+        base_dir,
+        home_dir,
+        client_os: &client_os::Type::Linux.into(),
+        machine: &Default::default(),
+        config_path,
+        child: true,
+      };
+
+      let err = map(&cx, &config, &mut vec![]).unwrap_err();
+      assert_eq!(
+        err.to_string(),
+        "`to` of `init.lua`: `${DTF_NO_SUCH_VAR}` is not defined"
+      );
+    }
+  }
 }
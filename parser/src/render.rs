@@ -1,3 +1,5 @@
+use crate::vars::{self, Vars};
+use std::borrow::Cow;
 use std::path::{Component, PathBuf};
 
 #[derive(Debug)]
@@ -5,6 +7,7 @@ pub struct RenderState<'a> {
   pub home_dir: &'a PathBuf,
   pub base_dir: &'a PathBuf,
   pub source_dir: &'a str,
+  pub vars: &'a Vars,
 }
 
 #[derive(Debug)]
@@ -13,9 +16,30 @@ pub struct Render<'a> {
 }
 
 impl<'a> Render<'a> {
-  pub fn normalize(&self, state: &RenderState) -> PathBuf {
-    let mut p = PathBuf::with_capacity(self.body.len() * 2);
-    let body = &PathBuf::from(self.body);
+  /// The body with its variables expanded, `$TARGET` being the source directory
+  pub fn expand(&self, state: &RenderState) -> Result<String, String> {
+    // the `Any` target has no directory, so its component goes away altogether
+    let body: Cow<str> = match state.source_dir.is_empty() {
+      true => Cow::Owned(
+        self
+          .body
+          .split('/')
+          .filter(|one| !matches!(*one, "$TARGET" | "${TARGET}"))
+          .collect::<Vec<&str>>()
+          .join("/"),
+      ),
+      false => Cow::Borrowed(self.body),
+    };
+
+    vars::expand(&body, &|name| match name {
+      "TARGET" => Some(state.source_dir),
+      name => state.vars.get(name),
+    })
+  }
+
+  fn normalize(body: &str, state: &RenderState) -> PathBuf {
+    let mut p = PathBuf::with_capacity(body.len() * 2);
+    let body = &PathBuf::from(body);
 
     if body.is_absolute() {
       p.push("/");
@@ -35,9 +59,9 @@ impl<'a> Render<'a> {
     p
   }
 
-  pub fn render(&self, state: &RenderState) -> PathBuf {
+  pub fn render(&self, state: &RenderState) -> Result<PathBuf, String> {
     let mut p = PathBuf::with_capacity(self.body.len() * 2);
-    let norm = &self.normalize(state);
+    let norm = &Self::normalize(&self.expand(state)?, state);
 
     for one in norm.components() {
       match one {
@@ -45,11 +69,6 @@ impl<'a> Render<'a> {
           "~" => {
             p.push(&state.home_dir);
           }
-          "$TARGET" => {
-            if !state.source_dir.is_empty() {
-              p.push(state.source_dir);
-            }
-          }
           val => {
             p.push(val);
           }
@@ -63,7 +82,7 @@ impl<'a> Render<'a> {
       }
     }
 
-    p
+    Ok(p)
   }
 }
 
@@ -0,0 +1,350 @@
+use dtflib::Context;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Defaults of the XDG base directory spec, under the home directory
+/// (<https://specifications.freedesktop.org/basedir-spec/latest/>)
+const XDG_DEFAULTS: &[(&str, &str)] = &[
+  ("XDG_CONFIG_HOME", ".config"),
+  ("XDG_DATA_HOME", ".local/share"),
+  ("XDG_STATE_HOME", ".local/state"),
+  ("XDG_CACHE_HOME", ".cache"),
+];
+
+/// Variables the paths of the config may refer to. The `vars:` table of the config
+/// goes first, then what dtf knows of the machine (`HOME`, `HOSTNAME`, `USER`, `ARCH`,
+/// `OS` and `OS_VERSION`), then the environment, then the XDG defaults. The values of
+/// the table are expanded too, they may refer to one another
+#[derive(Debug, Default)]
+pub struct Vars {
+  config: BTreeMap<String, String>,
+  own: HashMap<&'static str, String>,
+  env: HashMap<String, String>,
+  defaults: HashMap<&'static str, String>,
+}
+
+impl Vars {
+  pub fn new(cx: &Context, config: &BTreeMap<String, String>) -> Result<Self, String> {
+    Self::with_env(cx, config, std::env::vars().collect())
+  }
+
+  pub fn with_env(
+    cx: &Context,
+    config: &BTreeMap<String, String>,
+    env: HashMap<String, String>,
+  ) -> Result<Self, String> {
+    let path = |path: &Path| path.to_str().unwrap_or_default().to_owned();
+
+    let own = vec![
+      ("HOME", path(cx.home_dir)),
+      ("HOSTNAME", cx.machine.host.clone()),
+      ("USER", cx.machine.user.clone()),
+      ("ARCH", cx.machine.arch.clone()),
+      ("OS", cx.client_os.kind.dir().to_owned()),
+      (
+        "OS_VERSION",
+        cx.client_os
+          .version
+          .as_ref()
+          .map(ToString::to_string)
+          .unwrap_or_default(),
+      ),
+    ]
+    .into_iter()
+    // a fact dtf couldn't find out is not there, rather than empty
+    .filter(|(_, val)| !val.is_empty())
+    .collect();
+
+    let defaults = XDG_DEFAULTS
+      .iter()
+      .map(|(key, dir)| (*key, path(&cx.home_dir.join(dir))))
+      .collect();
+
+    let mut vars = Self {
+      config: BTreeMap::new(),
+      own,
+      env,
+      defaults,
+    };
+
+    for name in config.keys() {
+      vars.resolve(name, config, &mut vec![])?;
+    }
+
+    Ok(vars)
+  }
+
+  /// Expands a variable of the `vars:` table, the ones it refers to first
+  fn resolve(
+    &mut self,
+    name: &str,
+    config: &BTreeMap<String, String>,
+    stack: &mut Vec<String>,
+  ) -> Result<(), String> {
+    if self.config.contains_key(name) {
+      return Ok(());
+    }
+
+    if stack.iter().any(|one| one == name) {
+      stack.push(name.to_owned());
+      return Err(format!(
+        "`${}` is defined in terms of itself: {}",
+        name,
+        stack.join(" -> ")
+      ));
+    }
+
+    let body = &config[name];
+
+    // every name it mentions, defaults included, whether it comes to them or not
+    let mentioned = RefCell::new(vec![]);
+    let _ = expand(body, &|one| {
+      mentioned.borrow_mut().push(one.to_owned());
+      Some("")
+    });
+
+    stack.push(name.to_owned());
+    for one in mentioned.into_inner() {
+      if config.contains_key(&one) {
+        self.resolve(&one, config, stack)?;
+      }
+    }
+    stack.pop();
+
+    let val =
+      expand(body, &|one| self.get(one)).map_err(|e| format!("`{}` of `vars`: {}", name, e))?;
+    self.config.insert(name.to_owned(), val);
+
+    Ok(())
+  }
+
+  pub fn get(&self, name: &str) -> Option<&str> {
+    self
+      .config
+      .get(name)
+      .or_else(|| self.own.get(name))
+      // the XDG spec takes an empty variable for an unset one
+      .or_else(|| match (self.env.get(name), self.defaults.get(name)) {
+        (Some(val), Some(default)) if val.is_empty() => Some(default),
+        (Some(val), _) => Some(val),
+        (None, default) => default,
+      })
+      .map(String::as_str)
+  }
+}
+
+/// Expands `$VAR`, `${VAR}` and `${VAR:-default}` in `body`, `$$` stands for a `$`.
+/// A variable `lookup` knows nothing of, and that has no default, is an error
+pub fn expand<'a, F>(body: &str, lookup: &F) -> Result<String, String>
+where
+  F: Fn(&str) -> Option<&'a str>,
+{
+  let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+  let mut ret = String::with_capacity(body.len());
+  let mut rest = body;
+
+  while let Some(i) = rest.find('$') {
+    ret.push_str(&rest[..i]);
+    rest = &rest[i + 1..];
+
+    if let Some(after) = rest.strip_prefix('$') {
+      ret.push('$');
+      rest = after;
+      continue;
+    }
+
+    if let Some(after) = rest.strip_prefix('{') {
+      let end = closing(after).ok_or_else(|| format!("`${{` is never closed in `{}`", body))?;
+      let inner = &after[..end];
+      rest = &after[end + 1..];
+
+      let (name, default) = match inner.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (inner, None),
+      };
+
+      if name.is_empty() || !name.chars().all(is_name) {
+        return Err(format!("`${{{}}}` is not a variable", inner));
+      }
+
+      match (lookup(name), default) {
+        (Some(val), _) if !val.is_empty() => ret.push_str(val),
+        (_, Some(default)) => ret.push_str(&expand(default, lookup)?),
+        (Some(val), None) => ret.push_str(val),
+        (None, None) => return Err(format!("`${{{}}}` is not defined", name)),
+      }
+      continue;
+    }
+
+    let len = rest.find(|c| !is_name(c)).unwrap_or(rest.len());
+    let name = &rest[..len];
+    rest = &rest[len..];
+
+    match name {
+      // a lone `$` is left as it is
+      "" => ret.push('$'),
+      name => match lookup(name) {
+        Some(val) => ret.push_str(val),
+        None => return Err(format!("`${}` is not defined", name)),
+      },
+    }
+  }
+
+  ret.push_str(rest);
+  Ok(ret)
+}
+
+/// Where the `}` that closes a `${` is, the ones of nested defaults aside
+fn closing(val: &str) -> Option<usize> {
+  let mut depth = 0;
+
+  for (i, c) in val.char_indices() {
+    match c {
+      '{' => depth += 1,
+      '}' if depth == 0 => return Some(i),
+      '}' => depth -= 1,
+      _ => {}
+    }
+  }
+
+  None
+}
+
+#[cfg(test)]
+mod test_vars {
+  use super::{expand, Vars};
+  use dtflib::{client_os, Context, Machine};
+  use pretty_assertions::assert_eq;
+  use std::collections::{BTreeMap, HashMap};
+  use std::path::PathBuf;
+
+  fn lookup(name: &str) -> Option<&'static str> {
+    match name {
+      "HOME" => Some("/home/gman"),
+      "EMPTY" => Some(""),
+      _ => None,
+    }
+  }
+
+  #[test]
+  fn should_expand_every_form() {
+    assert_eq!(
+      expand("$HOME/.config", &lookup).unwrap(),
+      "/home/gman/.config"
+    );
+    assert_eq!(expand("${HOME}x", &lookup).unwrap(), "/home/gmanx");
+    assert_eq!(expand("${NOPE:-~/.x}", &lookup).unwrap(), "~/.x");
+    assert_eq!(expand("${EMPTY:-a}", &lookup).unwrap(), "a");
+    assert_eq!(
+      expand("${NOPE:-${HOME}/a}", &lookup).unwrap(),
+      "/home/gman/a"
+    );
+    assert_eq!(expand("${HOME:-}", &lookup).unwrap(), "/home/gman");
+    assert_eq!(expand("a$$b $ c", &lookup).unwrap(), "a$b $ c");
+  }
+
+  #[test]
+  fn should_tell_what_is_wrong() {
+    assert_eq!(
+      expand("$NOPE/a", &lookup).unwrap_err(),
+      "`$NOPE` is not defined"
+    );
+    assert_eq!(
+      expand("${NOPE}", &lookup).unwrap_err(),
+      "`${NOPE}` is not defined"
+    );
+    assert_eq!(
+      expand("${HOME/a", &lookup).unwrap_err(),
+      "`${` is never closed in `${HOME/a`"
+    );
+    assert_eq!(
+      expand("${a b}", &lookup).unwrap_err(),
+      "`${a b}` is not a variable"
+    );
+  }
+
+  #[test]
+  fn should_look_through_every_source_in_order() {
+    let home_dir = &PathBuf::from("/home/gman");
+    let client_os = &client_os::Os {
+      version: Some("22.04".parse().unwrap()),
+      ..client_os::Type::Ubuntu.into()
+    };
+    let machine = &Machine {
+      host: "work-1".to_owned(),
+      user: "gman".to_owned(),
+      arch: "x86_64".to_owned(),
+    };
+    let cx = Context {
+      config_path: &PathBuf::new(),
+      base_dir: &PathBuf::new(),
+      client_os,
+      machine,
+      home_dir,
+      child: true,
+    };
+
+    let config: BTreeMap<String, String> = [("ARCH".to_owned(), "amd64".to_owned())].into();
+    let env: HashMap<String, String> = [
+      ("USER".to_owned(), "root".to_owned()),
+      ("XDG_DATA_HOME".to_owned(), "/data".to_owned()),
+      ("XDG_CACHE_HOME".to_owned(), "".to_owned()),
+    ]
+    .into();
+
+    let vars = Vars::with_env(&cx, &config, env).unwrap();
+
+    assert_eq!(vars.get("ARCH"), Some("amd64"));
+    assert_eq!(vars.get("USER"), Some("gman"));
+    assert_eq!(vars.get("HOSTNAME"), Some("work-1"));
+    assert_eq!(vars.get("OS"), Some("ubuntu"));
+    assert_eq!(vars.get("OS_VERSION"), Some("22.04"));
+    assert_eq!(vars.get("XDG_DATA_HOME"), Some("/data"));
+    assert_eq!(vars.get("XDG_CONFIG_HOME"), Some("/home/gman/.config"));
+    assert_eq!(vars.get("XDG_CACHE_HOME"), Some("/home/gman/.cache"));
+    assert_eq!(vars.get("NOPE"), None);
+  }
+
+  #[test]
+  fn should_expand_the_table_itself() {
+    let cx = Context {
+      config_path: &PathBuf::new(),
+      base_dir: &PathBuf::new(),
+      client_os: &client_os::Type::Linux.into(),
+      machine: &Machine::default(),
+      home_dir: &PathBuf::from("/home/gman"),
+      child: true,
+    };
+    let table = |vars: &[(&str, &str)]| -> BTreeMap<String, String> {
+      vars
+        .iter()
+        .map(|(key, val)| (key.to_string(), val.to_string()))
+        .collect()
+    };
+
+    let vars = Vars::with_env(
+      &cx,
+      &table(&[
+        ("a", "$b/a"),
+        ("b", "${c}/b"),
+        ("c", "$HOME/c"),
+        ("d", "${NOPE:-$c}"),
+      ]),
+      HashMap::new(),
+    )
+    .unwrap();
+
+    assert_eq!(vars.get("a"), Some("/home/gman/c/b/a"));
+    assert_eq!(vars.get("d"), Some("/home/gman/c"));
+
+    assert_eq!(
+      Vars::with_env(&cx, &table(&[("a", "$b"), ("b", "x/${a}")]), HashMap::new()).unwrap_err(),
+      "`$a` is defined in terms of itself: a -> b -> a"
+    );
+    assert_eq!(
+      Vars::with_env(&cx, &table(&[("a", "$NOPE")]), HashMap::new()).unwrap_err(),
+      "`a` of `vars`: `$NOPE` is not defined"
+    );
+  }
+}
@@ -0,0 +1,23 @@
+use std::{fs, io, path::Path};
+
+/// Writes the value back in the format picked by the file extension, the same way
+/// `read_file` picks a parser
+pub fn write_file<T: serde::Serialize>(path: &Path, val: &T) -> io::Result<()> {
+  let content = match path
+    .extension()
+    .expect("config has no extension")
+    .to_str()
+    .unwrap()
+  {
+    "json" => serde_json::to_string_pretty(val)? + "\n",
+    "yml" | "yaml" => serde_yaml::to_string(val).map_err(io::Error::other)? + "\n",
+    "toml" => {
+      // values have to come before tables in toml, which only `Value` takes care of
+      let val = toml::Value::try_from(val).map_err(io::Error::other)?;
+      toml::to_string(&val).map_err(io::Error::other)?
+    }
+    _ => return Err(io::Error::other("Unsupported config extension")),
+  };
+
+  fs::write(path, content)
+}
@@ -0,0 +1,22 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir dist
+}
+
+cmd() {
+  echo "dtf ln dtf.yml --mode symlink --os macos"
+}
+
+assert() {
+  test -L dist/file.txt
+  test "$(readlink dist/file.txt)" = "$(pwd)/files/macos/file.txt"
+}
+
+name() {
+  echo "it should make an absolute symbolic link when run with --mode symlink"
+}
+
+main "$1"
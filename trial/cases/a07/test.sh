@@ -0,0 +1,22 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir dist
+}
+
+cmd() {
+  echo "dtf ln dtf.yml --os macos"
+}
+
+assert() {
+  test -L dist/file.txt
+  test "$(readlink dist/file.txt)" = "../files/macos/file.txt"
+}
+
+name() {
+  echo "it should make a relative symbolic link when the file asks for one"
+}
+
+main "$1"
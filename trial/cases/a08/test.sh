@@ -0,0 +1,23 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir dist
+}
+
+cmd() {
+  echo "dtf ln dtf.yml --os macos"
+}
+
+assert() {
+  test -f dist/file.txt || exit 1
+  test dist/file.txt -ef files/macos/file.txt && exit 1
+  cmp -s dist/file.txt files/macos/file.txt
+}
+
+name() {
+  echo "it should make an independent copy when the file asks for one"
+}
+
+main "$1"
@@ -0,0 +1,24 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo original > dist/repo/file.txt
+  dtf ln dtf.yml --os macos || exit 1
+  echo edited > dist/home/file.txt
+}
+
+cmd() {
+  echo "dtf pull dtf.yml --os macos"
+}
+
+assert() {
+  test "$(cat dist/repo/file.txt)" = "edited"
+}
+
+name() {
+  echo "it should pull an edited copy back into the repo"
+}
+
+main "$1"
@@ -0,0 +1,23 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir dist
+  dtf ln dtf.yml --os macos
+}
+
+cmd() {
+  echo "dtf unlink dtf.yml --os macos"
+}
+
+assert() {
+  test -f dist/file.txt && exit 1
+  test -f files/macos/file.txt
+}
+
+name() {
+  echo "it should remove what it has linked and leave the source alone"
+}
+
+main "$1"
@@ -0,0 +1,24 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir dist
+  dtf ln dtf.yml --os macos || exit 1
+  rm dist/file.txt
+  echo edited > dist/file.txt
+}
+
+cmd() {
+  echo "dtf repair dtf.yml --prefer src --os macos"
+}
+
+assert() {
+  test dist/file.txt -ef files/macos/file.txt
+}
+
+name() {
+  echo "it should re-link a destination that was saved as a new file"
+}
+
+main "$1"
@@ -0,0 +1,24 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  printf 'map:\n  - target:\n      - macos\n    files: []\n' > dist/repo/dtf.yml
+  echo mine > dist/home/file.txt
+}
+
+cmd() {
+  echo "dtf adopt dist/repo/dtf.yml dist/home/file.txt --os macos"
+}
+
+assert() {
+  test dist/home/file.txt -ef dist/repo/files/macos/file.txt || exit 1
+  grep -q "name: file.txt" dist/repo/dtf.yml
+}
+
+name() {
+  echo "it should move the file into the repo, add it to the config and link it back"
+}
+
+main "$1"
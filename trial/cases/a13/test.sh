@@ -0,0 +1,23 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir dist
+  echo mine > dist/file.txt
+  dtf ln dtf.yml -f --os macos
+}
+
+cmd() {
+  echo "dtf restore $(ls dist/.state/dtf/backups)"
+}
+
+assert() {
+  test "$(cat dist/file.txt)" = "mine"
+}
+
+name() {
+  echo "it should back up a destination overwritten with -f and restore it back"
+}
+
+main "$1"
@@ -0,0 +1,28 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo one > dist/repo/one.txt
+  echo two > dist/repo/two.txt
+  printf 'map:\n  - from: repo\n    files:\n      - name: one.txt\n        to: home\n      - name: two.txt\n        to: home\n' > dist/dtf.yml
+  dtf ln dist/dtf.yml --os macos || exit 1
+  printf 'map:\n  - from: repo\n    files:\n      - name: one.txt\n        to: home\n' > dist/dtf.yml
+}
+
+cmd() {
+  echo "dtf ln dist/dtf.yml -f --prune --os macos"
+}
+
+assert() {
+  test dist/home/one.txt -ef dist/repo/one.txt || exit 1
+  test -f dist/home/two.txt && exit 1
+  test -f dist/repo/two.txt
+}
+
+name() {
+  echo "it should remove links of files that were removed from the config"
+}
+
+main "$1"
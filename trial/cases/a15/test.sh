@@ -0,0 +1,27 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo one > dist/repo/one.txt
+  echo two > dist/repo/two.txt
+  echo old > dist/home/two.txt
+  printf 'map:\n  - from: repo\n    files:\n      - name: one.txt\n        to: home\n      - name: two.txt\n        to: home\n' > dist/dtf.yml
+}
+
+cmd() {
+  echo "dtf ln dist/dtf.yml -f --dry-run --os macos"
+}
+
+assert() {
+  test -e dist/home/one.txt && exit 1
+  test dist/home/two.txt -ef dist/repo/two.txt && exit 1
+  grep -q old dist/home/two.txt
+}
+
+name() {
+  echo "it should only print the plan on a dry run"
+}
+
+main "$1"
@@ -0,0 +1,31 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home/three.txt
+  echo one > dist/repo/one.txt
+  echo two > dist/repo/two.txt
+  echo three > dist/repo/three.txt
+  echo old > dist/home/one.txt
+  printf 'map:\n  - from: repo\n    files:\n      - name: one.txt\n        to: home\n      - name: two.txt\n        to: home\n      - name: three.txt\n        to: home\n' > dist/dtf.yml
+  # a directory in the way of three.txt can't be backed up, so the run fails
+  dtf ln dist/dtf.yml -f --atomic --os macos && exit 1 || exit 0
+}
+
+cmd() {
+  echo "dtf rollback"
+}
+
+assert() {
+  test -e dist/home/two.txt && exit 1
+  test dist/home/one.txt -ef dist/repo/one.txt && exit 1
+  grep -q old dist/home/one.txt || exit 1
+  test -e dist/.state/dtf/journal.jsonl && exit 1 || exit 0
+}
+
+name() {
+  echo "it should take everything back when an atomic run fails"
+}
+
+main "$1"
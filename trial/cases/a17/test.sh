@@ -0,0 +1,31 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo one > dist/repo/one.txt
+  echo two > dist/repo/two.txt
+  printf 'map:\n  - from: repo\n    files:\n      - name: one.txt\n        to: home/.config/one\n  - from: repo\n    mkdir: true\n    files:\n      - name: two.txt\n        to: home/.config/two\n' > dist/dtf.yml
+  dtf ln dist/dtf.yml --os macos && exit 1
+  test -d dist/home/.config/one && exit 1
+  test dist/home/.config/two/two.txt -ef dist/repo/two.txt || exit 1
+  rm -rf dist/home/.config
+  dtf ln dist/dtf.yml --create-dirs --dir-mode 700 --os macos || exit 1
+  test "$(stat -c %a dist/home/.config/one)" = 700 || exit 1
+}
+
+cmd() {
+  echo "dtf unlink dist/dtf.yml --os macos"
+}
+
+assert() {
+  test -e dist/home/.config && exit 1
+  test -f dist/repo/one.txt
+}
+
+name() {
+  echo "it should create missing destination directories and remove them on unlink"
+}
+
+main "$1"
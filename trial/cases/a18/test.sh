@@ -0,0 +1,27 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo/nvim/lua dist/home
+  echo init > dist/repo/nvim/init.lua
+  echo options > dist/repo/nvim/lua/options.lua
+  echo log > dist/repo/nvim/debug.log
+  printf 'map:\n  - from: repo\n    files:\n      - name: nvim/\n        to: home/.config\n        recursive: true\n        exclude:\n          - "*.log"\n' > dist/dtf.yml
+}
+
+cmd() {
+  echo "dtf ln dist/dtf.yml --os macos"
+}
+
+assert() {
+  test dist/home/.config/nvim/init.lua -ef dist/repo/nvim/init.lua || exit 1
+  test dist/home/.config/nvim/lua/options.lua -ef dist/repo/nvim/lua/options.lua || exit 1
+  test -e dist/home/.config/nvim/debug.log && exit 1 || exit 0
+}
+
+name() {
+  echo "it should mirror the tree of a recursive block file by file"
+}
+
+main "$1"
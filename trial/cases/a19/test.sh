@@ -0,0 +1,30 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo one > dist/repo/one.zsh
+  echo two > dist/repo/two.zsh
+  echo three > dist/repo/three.txt
+  printf 'map:\n  - from: repo\n    files:\n      - name: "*.zsh"\n        to: home\n      - name: "*.fish"\n        to: home\n' > dist/dtf.yml
+}
+
+cmd() {
+  echo "dtf ln dist/dtf.yml --os macos"
+}
+
+assert() {
+  local out
+  test dist/home/one.zsh -ef dist/repo/one.zsh || exit 1
+  test dist/home/two.zsh -ef dist/repo/two.zsh || exit 1
+  test -e dist/home/three.txt && exit 1
+  out=$(dtf ls dist/dtf.yml --os macos 2>&1)
+  echo "$out" | grep -q 'matches no files'
+}
+
+name() {
+  echo "it should link every file matched by a pattern"
+}
+
+main "$1"
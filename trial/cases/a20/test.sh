@@ -0,0 +1,27 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo zsh > dist/repo/zshrc
+  printf 'map:\n  - from: repo\n    files:\n      - name: zshrc\n        to: home\n        as: .zshrc\n' > dist/dtf.yml
+}
+
+cmd() {
+  echo "dtf ln dist/dtf.yml --os macos"
+}
+
+assert() {
+  local out
+  test dist/home/.zshrc -ef dist/repo/zshrc || exit 1
+  test -e dist/home/zshrc && exit 1
+  out=$(dtf ls dist/dtf.yml --os macos)
+  echo "$out" | grep -q 'as .zshrc'
+}
+
+name() {
+  echo "it should link the file under the name given with as"
+}
+
+main "$1"
@@ -0,0 +1,31 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo/ubuntu dist/repo/linux dist/home
+  echo ubuntu > dist/repo/ubuntu/aliases
+  echo linux > dist/repo/linux/aliases
+  echo linux > dist/repo/linux/env
+  echo any > dist/repo/profile
+  printf 'map:\n  - from: repo/$TARGET\n    target: [ubuntu, linux, any]\n    files:\n      - name: aliases\n        to: home\n      - name: env\n        to: home\n      - name: profile\n        to: home\n' > dist/dtf.yml
+}
+
+cmd() {
+  echo "dtf ln dist/dtf.yml --os ubuntu"
+}
+
+assert() {
+  local out
+  test dist/home/aliases -ef dist/repo/ubuntu/aliases || exit 1
+  test dist/home/env -ef dist/repo/linux/env || exit 1
+  test dist/home/profile -ef dist/repo/profile || exit 1
+  out=$(dtf ls dist/dtf.yml --os ubuntu --explain)
+  echo "$out" | grep -q 'found, but outranked'
+}
+
+name() {
+  echo "it should take each file from the most specific target that has it"
+}
+
+main "$1"
@@ -0,0 +1,30 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo new > dist/repo/new
+  echo old > dist/repo/old
+  printf 'map:\n  - from: repo\n    target: ["ubuntu>=22.04"]\n    files:\n      - name: new\n        to: home\n  - from: repo\n    target: ["ubuntu<22.04"]\n    files:\n      - name: old\n        to: home\n' > dist/dtf.yml
+}
+
+cmd() {
+  echo "dtf ln dist/dtf.yml --os ubuntu --os-version 24.04"
+}
+
+assert() {
+  local out
+  test dist/home/new -ef dist/repo/new || exit 1
+  test -e dist/home/old && exit 1
+  out=$(dtf ls dist/dtf.yml --os ubuntu --os-version 20.04)
+  echo "$out" | grep -q '^old' || exit 1
+  echo "$out" | grep -q '^new' && exit 1
+  true
+}
+
+name() {
+  echo "it should only link the files of the sections meant for the version"
+}
+
+main "$1"
@@ -0,0 +1,30 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p 'dist/repo/host/work-*' dist/repo/arch/aarch64 dist/home
+  echo work > 'dist/repo/host/work-*'/gitconfig
+  echo any > dist/repo/gitconfig
+  echo arm > dist/repo/arch/aarch64/toolchain
+  echo any > dist/repo/toolchain
+  printf 'map:\n  - from: repo/$TARGET\n    target: ["host:work-*", "arch:aarch64", any]\n    files:\n      - name: gitconfig\n        to: home\n      - name: toolchain\n        to: home\n' > dist/dtf.yml
+}
+
+cmd() {
+  echo "dtf ln dist/dtf.yml --host work-laptop --arch x86_64"
+}
+
+assert() {
+  local out
+  test dist/home/gitconfig -ef 'dist/repo/host/work-*'/gitconfig || exit 1
+  test dist/home/toolchain -ef dist/repo/toolchain || exit 1
+  out=$(dtf ls dist/dtf.yml --host home --arch arm64)
+  echo "$out" | grep -q 'repo/arch/aarch64/toolchain'
+}
+
+name() {
+  echo "it should pick the sources of the machine given with --host and --arch"
+}
+
+main "$1"
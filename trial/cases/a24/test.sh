@@ -0,0 +1,27 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo/srv dist/home
+  echo server > dist/repo/srv/profile
+  echo any > dist/repo/profile
+  printf 'targets:\n  server:\n    target: "host:srv-*"\n    dir: srv\nmap:\n  - from: repo/$TARGET\n    target: [server, linux, macos]\n    files:\n      - name: profile\n        to: home\n' > dist/dtf.yml
+}
+
+cmd() {
+  echo "dtf ln dist/dtf.yml --host srv-1"
+}
+
+assert() {
+  local out
+  test dist/home/profile -ef dist/repo/srv/profile || exit 1
+  out=$(dtf ls dist/dtf.yml --host srv-1)
+  echo "$out" | grep -q 'repo/srv/profile'
+}
+
+name() {
+  echo "it should pick the source directory of a target of the targets table"
+}
+
+main "$1"
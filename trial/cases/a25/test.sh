@@ -0,0 +1,30 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo kitty > dist/repo/kitty.conf
+  echo gnome > dist/repo/gnome.conf
+  printf 'map:\n  - from: repo\n    files:\n      - name: kitty.conf\n        to: home\n        when:\n          command: sh\n      - name: gnome.conf\n        to: home\n        when:\n          env_equals:\n            DTF_TRIAL_DESKTOP: GNOME\n' > dist/dtf.yml
+}
+
+cmd() {
+  echo "dtf ln dist/dtf.yml"
+}
+
+assert() {
+  local out
+  test dist/home/kitty.conf -ef dist/repo/kitty.conf || exit 1
+  test ! -e dist/home/gnome.conf || exit 1
+  out=$(dtf ls dist/dtf.yml --explain)
+  echo "$out" | grep -q 'DTF_TRIAL_DESKTOP is not set' || exit 1
+  out=$(DTF_TRIAL_DESKTOP=GNOME dtf ls dist/dtf.yml)
+  echo "$out" | grep -q 'repo/gnome.conf'
+}
+
+name() {
+  echo "it should skip the blocks whose conditions don't hold and tell why"
+}
+
+main "$1"
@@ -0,0 +1,27 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home/.config/app
+  echo app > dist/repo/app.conf
+  printf 'vars:\n  HOME_DIR: home\nmap:\n  - from: repo\n    files:\n      - name: app.conf\n        to: ${HOME_DIR}/.config/${APP:-app}\n' > dist/dtf.yml
+  printf 'map:\n  - from: repo\n    files:\n      - name: app.conf\n        to: ${DTF_TRIAL_NO_SUCH_VAR}/app\n' > dist/broken.yml
+}
+
+cmd() {
+  echo "dtf ln dist/dtf.yml"
+}
+
+assert() {
+  local out
+  test dist/home/.config/app/app.conf -ef dist/repo/app.conf || exit 1
+  out=$(dtf ls dist/broken.yml 2>&1)
+  echo "$out" | grep -q 'app.conf.*DTF_TRIAL_NO_SUCH_VAR.*is not defined'
+}
+
+name() {
+  echo "it should expand the variables of the paths and refuse undefined ones"
+}
+
+main "$1"
@@ -0,0 +1,29 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo original > dist/repo/a.txt
+  echo original > dist/repo/b.txt
+  dtf ln dtf.yml --os macos || exit 1
+  echo edited > dist/home/b.txt
+  rm dist/repo/a.txt
+}
+
+cmd() {
+  echo "dtf ls dtf.yml --os macos"
+}
+
+assert() {
+  local out
+  out=$(dtf pull dtf.yml --os macos) && exit 1
+  echo "$out" | grep -q 'a.txt' || exit 1
+  test "$(cat dist/repo/b.txt)" = "edited"
+}
+
+name() {
+  echo "it should pull the other copies when one source is missing"
+}
+
+main "$1"
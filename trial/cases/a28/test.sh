@@ -0,0 +1,29 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo same > dist/repo/a.txt
+  echo same > dist/repo/b.txt
+  dtf ln dtf.yml --os macos || exit 1
+  rm dist/home/a.txt
+  echo same > dist/home/a.txt
+}
+
+cmd() {
+  echo "dtf ls dtf.yml --os macos"
+}
+
+assert() {
+  dtf unlink dtf.yml --os macos && exit 1
+  test -f dist/home/a.txt || exit 1
+  test -e dist/home/b.txt && exit 1
+  test -f dist/repo/b.txt
+}
+
+name() {
+  echo "it should only unlink what it linked, copies by their recorded hash"
+}
+
+main "$1"
@@ -0,0 +1,35 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  for one in a b c d; do echo $one > dist/repo/$one.txt; done
+  dtf ln dtf.yml --os macos || exit 1
+  dtf status dtf.yml --os macos || exit 1
+  rm dist/home/b.txt
+  rm dist/home/c.txt
+  echo c > dist/home/c.txt
+  ln -sf ../repo/a.txt dist/home/d.txt
+}
+
+cmd() {
+  echo "dtf ls dtf.yml --os macos"
+}
+
+assert() {
+  local out
+  out=$(dtf status dtf.yml --os macos --porcelain) && exit 1
+  echo "$out" | grep -q "^linked	.*/a.txt	" || exit 1
+  echo "$out" | grep -q "^missing-dst	.*/b.txt	" || exit 1
+  echo "$out" | grep -q "^diverged	.*/c.txt	" || exit 1
+  echo "$out" | grep -q "^wrong-target	.*/d.txt	" || exit 1
+  out=$(dtf status dtf.yml --os macos) && exit 1
+  echo "$out" | grep -q "same content, but not linked" || exit 1
+}
+
+name() {
+  echo "it should tell the state of every dotfile and fail unless all are linked"
+}
+
+main "$1"
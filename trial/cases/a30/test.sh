@@ -0,0 +1,26 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo a > dist/repo/a.txt
+  dtf ln dtf.yml --os macos || exit 1
+  test dist/home/a.txt -ef dist/repo/a.txt
+}
+
+cmd() {
+  echo "dtf ln dtf.yml --os macos --mode symlink --force"
+}
+
+assert() {
+  test -L dist/home/a.txt || exit 1
+  test "$(cat dist/home/a.txt)" = "a" || exit 1
+  test -z "$(ls -A dist/.state/backups 2>/dev/null)"
+}
+
+name() {
+  echo "it should relink a hard link to the source without backing it up"
+}
+
+main "$1"
@@ -0,0 +1,30 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home dist/.state/dtf
+  echo a > dist/repo/a.txt
+  echo b > dist/home/b.txt
+  printf '{"Created":{"path":"%s"}}\n' "$PWD/dist/home/b.txt" > dist/.state/dtf/journal.jsonl
+  dtf ln dtf.yml --atomic --os macos < /dev/null && exit 1
+  test -e dist/home/a.txt && exit 1
+  test -f dist/.state/dtf/journal.jsonl
+}
+
+cmd() {
+  echo "dtf rollback"
+}
+
+assert() {
+  test -e dist/home/b.txt && exit 1
+  test -e dist/.state/dtf/journal.jsonl && exit 1
+  dtf ln dtf.yml --atomic --os macos < /dev/null || exit 1
+  test dist/home/a.txt -ef dist/repo/a.txt
+}
+
+name() {
+  echo "it should keep a leftover journal until it is rolled back"
+}
+
+main "$1"
@@ -0,0 +1,32 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo work > dist/repo/gitconfig
+  printf 'map:\n  - from: repo\n    target: ["host:work-*"]\n    files:\n      - name: gitconfig\n        to: home\n' > dist/dtf.yml
+  dtf ln dist/dtf.yml --host work-1 || exit 1
+  test dist/home/gitconfig -ef dist/repo/gitconfig
+}
+
+cmd() {
+  echo "dtf status dist/dtf.yml --host work-1"
+}
+
+assert() {
+  local out
+  out=$(dtf status dist/dtf.yml --host work-1 --porcelain)
+  echo "$out" | grep -q '^linked	' || exit 1
+  dtf unlink dist/dtf.yml --host home || exit 1
+  test -e dist/home/gitconfig || exit 1
+  dtf unlink dist/dtf.yml --host work-1 || exit 1
+  test -e dist/home/gitconfig && exit 1
+  test -f dist/repo/gitconfig
+}
+
+name() {
+  echo "it should take the machine from the command line on every command"
+}
+
+main "$1"
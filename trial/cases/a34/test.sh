@@ -0,0 +1,30 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/repo dist/home
+  echo one > dist/repo/one.txt
+  echo two > dist/repo/two.txt
+  printf 'map:\n  - from: repo\n    files:\n      - name: one.txt\n        to: home\n      - name: two.txt\n        to: home\n        when:\n          env: DTF_TRIAL_A34\n' > dist/dtf.yml
+  DTF_TRIAL_A34=1 dtf ln dist/dtf.yml --os macos || exit 1
+}
+
+cmd() {
+  echo "dtf prune dist/dtf.yml --os macos"
+}
+
+assert() {
+  local out
+  dtf ln dist/dtf.yml --prune --os macos || exit 1
+  test dist/home/one.txt -ef dist/repo/one.txt || exit 1
+  test dist/home/two.txt -ef dist/repo/two.txt || exit 1
+  out=$(dtf ls dist/dtf.yml --explain --os macos)
+  echo "$out" | grep -q "DTF_TRIAL_A34 is not set"
+}
+
+name() {
+  echo "it should not prune the links of a block skipped on its conditions"
+}
+
+main "$1"
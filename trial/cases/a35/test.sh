@@ -0,0 +1,25 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/home/dir dist/.state/dtf
+  printf '{"Created":{"path":"%s"}}\n' "$PWD/dist/home/dir" > dist/.state/dtf/journal.jsonl
+  dtf rollback && exit 1
+  test -f dist/.state/dtf/journal.jsonl
+}
+
+cmd() {
+  echo "dtf backups list"
+}
+
+assert() {
+  test -d dist/home/dir || exit 1
+  test -f dist/.state/dtf/journal.jsonl
+}
+
+name() {
+  echo "it should keep the journal when something can't be rolled back"
+}
+
+main "$1"
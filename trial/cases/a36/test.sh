@@ -0,0 +1,25 @@
+#!/bin/bash
+
+source ../../lib.sh
+
+setup() {
+  mkdir -p dist/.state/dtf/backups
+}
+
+cmd() {
+  echo "dtf backups list"
+}
+
+assert() {
+  local out
+  out=$(dtf restore ../backups 2>&1) && exit 1
+  echo "$out" | grep -q "is not a run id" || exit 1
+  out=$(dtf restore a/b 2>&1) && exit 1
+  echo "$out" | grep -q "is not a run id"
+}
+
+name() {
+  echo "it should only restore run ids that name a run"
+}
+
+main "$1"
@@ -25,6 +25,9 @@ run() {
 
   cd "$base_dir"
 
+  # keep whatever dtf remembers between runs away from the real home
+  export XDG_STATE_HOME="$base_dir/dist/.state"
+
   local name=$($test name)
   test -n "$name" && echo "> $name"
 